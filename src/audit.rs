@@ -0,0 +1,195 @@
+use md5::Md5;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Retention settings recorded in the audit file, as written by winston-daily-rotate-file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditKeep {
+    pub days: bool,
+    pub amount: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// Creation time in milliseconds since the Unix epoch.
+    pub date: i64,
+    pub name: String,
+    pub hash: String,
+}
+
+/// The JSON audit file listing every log file the transport created, compatible with the
+/// `auditFile` of winston-daily-rotate-file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLog {
+    pub keep: Option<AuditKeep>,
+    pub audit_log: String,
+    pub files: Vec<AuditEntry>,
+    pub hash_type: String,
+}
+
+impl AuditLog {
+    /// Reads the audit file at `path`, or starts an empty one if it does not exist yet.
+    ///
+    /// New entries are hashed with the file's `hashType`, so audit files written by
+    /// winston-daily-rotate-file, which defaults to md5, stay consistent.
+    pub fn load(path: &Path, keep: Option<AuditKeep>) -> std::io::Result<Self> {
+        let mut audit = match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice::<AuditLog>(&bytes)
+                .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == ErrorKind::NotFound => AuditLog {
+                keep: None,
+                audit_log: path.display().to_string(),
+                files: Vec::new(),
+                hash_type: "sha256".to_string(),
+            },
+            Err(e) => return Err(e),
+        };
+
+        if !matches!(audit.hash_type.as_str(), "sha256" | "md5") {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("unsupported audit hashType '{}'", audit.hash_type),
+            ));
+        }
+
+        audit.keep = keep;
+        Ok(audit)
+    }
+
+    /// Writes the audit file, replacing the previous version atomically.
+    pub fn save(&self) -> std::io::Result<()> {
+        let path = Path::new(&self.audit_log);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| std::io::Error::new(ErrorKind::InvalidData, e))?;
+
+        let mut tmp_name = path.as_os_str().to_owned();
+        tmp_name.push(".tmp");
+        let tmp_path = PathBuf::from(tmp_name);
+        fs::write(&tmp_path, json)?;
+        fs::rename(tmp_path, path)
+    }
+
+    pub fn contains(&self, path: &Path) -> bool {
+        let name = path.display().to_string();
+        self.files.iter().any(|entry| entry.name == name)
+    }
+
+    /// Records a newly created log file.
+    pub fn add(&mut self, path: &Path, date_millis: i64) {
+        let name = path.display().to_string();
+        let data = format!("{}LOG_FILE{}", name, date_millis);
+        let hash = match self.hash_type.as_str() {
+            "md5" => hex(&Md5::digest(data.as_bytes())),
+            _ => hex(&Sha256::digest(data.as_bytes())),
+        };
+
+        self.files.push(AuditEntry {
+            date: date_millis,
+            name,
+            hash,
+        });
+    }
+
+    /// Points the entry for `from` at `to`, e.g. after the file has been compressed.
+    pub fn rename(&mut self, from: &Path, to: &Path) {
+        let from = from.display().to_string();
+        for entry in self.files.iter_mut().filter(|entry| entry.name == from) {
+            entry.name = to.display().to_string();
+        }
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        let name = path.display().to_string();
+        self.files.retain(|entry| entry.name != name);
+    }
+}
+
+/// Lowercase hex encoding of a digest, used for the `hash` of each audit entry.
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_digests() {
+        assert_eq!(
+            hex(&Sha256::digest(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            hex(&Md5::digest(b"abc")),
+            "900150983cd24fb0d6963f7d28e17f72"
+        );
+    }
+
+    #[test]
+    fn test_node_md5_audit_file_keeps_its_hash_type() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp directory");
+        let audit_path = temp_dir.path().join(".audit.json");
+        // As written by winston-daily-rotate-file with its default hashType
+        fs::write(
+            &audit_path,
+            r#"{
+    "keep": {"days": false, "amount": 3},
+    "auditLog": "logs/.audit.json",
+    "files": [
+        {
+            "date": 1704067200000,
+            "name": "logs/app.log.2024-01-01",
+            "hash": "0f1d2b2d3c7e6bd8bb1c0e9a5d3e2c41"
+        }
+    ],
+    "hashType": "md5"
+}"#,
+        )
+        .unwrap();
+
+        let mut audit = AuditLog::load(&audit_path, None).expect("Failed to load audit file");
+        audit.add(Path::new("logs/app.log.2024-01-02"), 1_704_153_600_000);
+
+        assert_eq!(audit.hash_type, "md5");
+        assert_eq!(
+            audit.files[1].hash,
+            hex(&Md5::digest(
+                b"logs/app.log.2024-01-02LOG_FILE1704153600000"
+            ))
+        );
+
+        fs::write(
+            &audit_path,
+            r#"{"keep": null, "auditLog": "a", "files": [], "hashType": "sha1"}"#,
+        )
+        .unwrap();
+        assert!(AuditLog::load(&audit_path, None).is_err());
+    }
+
+    #[test]
+    fn test_audit_round_trip() {
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp directory");
+        let audit_path = temp_dir.path().join(".audit.json");
+
+        let mut audit = AuditLog::load(&audit_path, None).expect("Failed to load audit file");
+        audit.add(Path::new("logs/app.log.2024-01-01"), 1_704_067_200_000);
+        audit.save().expect("Failed to save audit file");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&audit_path).unwrap()).unwrap();
+        assert_eq!(json["hashType"], "sha256");
+        assert_eq!(json["files"][0]["name"], "logs/app.log.2024-01-01");
+        assert_eq!(json["files"][0]["date"], 1_704_067_200_000i64);
+
+        let reloaded = AuditLog::load(&audit_path, None).expect("Failed to reload audit file");
+        assert!(reloaded.contains(Path::new("logs/app.log.2024-01-01")));
+    }
+}
@@ -0,0 +1,232 @@
+use crate::error::DailyRotateFileError;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+/// Extensions of every codec this crate can write, whether or not its feature is enabled, so
+/// cleanup still recognizes archives written by a build with other features.
+const ARCHIVE_EXTENSIONS: [&str; 5] = ["gz", "zst", "xz", "bz2", "lz4"];
+
+/// The codec used to archive rotated files.
+///
+/// Non-exhaustive because cargo features anywhere in the dependency graph add variants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compression {
+    /// gzip with a level from 0 (none) to 9 (best).
+    Gzip { level: u32 },
+    /// Zstandard with a level from 1 to 22.
+    #[cfg(feature = "zstd")]
+    Zstd { level: i32 },
+    /// xz (LZMA2) with a level from 0 to 9.
+    #[cfg(feature = "xz")]
+    Xz { level: u32 },
+    /// bzip2 with a level from 1 to 9.
+    #[cfg(feature = "bzip2")]
+    Bzip2 { level: u32 },
+    /// LZ4 frame format.
+    #[cfg(feature = "lz4")]
+    Lz4,
+}
+
+impl Default for Compression {
+    /// gzip at level 6, the zlib default.
+    fn default() -> Self {
+        Compression::Gzip { level: 6 }
+    }
+}
+
+impl Compression {
+    /// The extension appended to archived files, without the leading dot.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Compression::Gzip { .. } => "gz",
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { .. } => "zst",
+            #[cfg(feature = "xz")]
+            Compression::Xz { .. } => "xz",
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 { .. } => "bz2",
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => "lz4",
+        }
+    }
+
+    /// Checks the level against the range the codec accepts; the encoders panic outside it.
+    pub(crate) fn validate(&self) -> Result<(), DailyRotateFileError> {
+        let (codec, level, range) = match *self {
+            Compression::Gzip { level } => ("gzip", level as i64, 0..=9),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { level } => ("zstd", level as i64, 1..=22),
+            #[cfg(feature = "xz")]
+            Compression::Xz { level } => ("xz", level as i64, 0..=9),
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 { level } => ("bzip2", level as i64, 1..=9),
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => return Ok(()),
+        };
+
+        if range.contains(&level) {
+            Ok(())
+        } else {
+            Err(DailyRotateFileError::InvalidConfig(format!(
+                "{} compression level must be between {} and {}, got {}",
+                codec,
+                range.start(),
+                range.end(),
+                level
+            )))
+        }
+    }
+
+    /// Whether `path` carries the extension of any supported codec.
+    pub fn is_archive(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ARCHIVE_EXTENSIONS.contains(&ext))
+    }
+
+    /// `file_name` without its archive extension, if it has one.
+    pub(crate) fn strip_archive_extension(file_name: &str) -> &str {
+        ARCHIVE_EXTENSIONS
+            .iter()
+            .find_map(|ext| {
+                file_name
+                    .strip_suffix(ext)
+                    .and_then(|rest| rest.strip_suffix('.'))
+            })
+            .unwrap_or(file_name)
+    }
+
+    /// Compresses all of `input` into `output` and returns the finished output file.
+    pub(crate) fn compress<R: Read>(&self, input: &mut R, output: File) -> std::io::Result<File> {
+        match *self {
+            Compression::Gzip { level } => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(output, flate2::Compression::new(level));
+                std::io::copy(input, &mut encoder)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { level } => {
+                let mut encoder = zstd::stream::write::Encoder::new(output, level)?;
+                std::io::copy(input, &mut encoder)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "xz")]
+            Compression::Xz { level } => {
+                let mut encoder = xz2::write::XzEncoder::new(output, level);
+                std::io::copy(input, &mut encoder)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 { level } => {
+                let level = bzip2::Compression::try_new(level).ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("invalid bzip2 compression level {}", level),
+                    )
+                })?;
+                let mut encoder = bzip2::write::BzEncoder::new(output, level);
+                std::io::copy(input, &mut encoder)?;
+                encoder.finish()
+            }
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => {
+                let mut encoder = lz4_flex::frame::FrameEncoder::new(output);
+                std::io::copy(input, &mut encoder)?;
+                encoder.finish().map_err(std::io::Error::from)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decompress(compression: Compression, archive: File) -> String {
+        let mut contents = String::new();
+        let mut decoder: Box<dyn Read> = match compression {
+            Compression::Gzip { .. } => Box::new(flate2::read::GzDecoder::new(archive)),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { .. } => {
+                Box::new(zstd::stream::read::Decoder::new(archive).unwrap())
+            }
+            #[cfg(feature = "xz")]
+            Compression::Xz { .. } => Box::new(xz2::read::XzDecoder::new(archive)),
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 { .. } => Box::new(bzip2::read::BzDecoder::new(archive)),
+            #[cfg(feature = "lz4")]
+            Compression::Lz4 => Box::new(lz4_flex::frame::FrameDecoder::new(archive)),
+        };
+        decoder.read_to_string(&mut contents).unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_round_trip_for_enabled_codecs() {
+        let codecs = [
+            Compression::default(),
+            #[cfg(feature = "zstd")]
+            Compression::Zstd { level: 3 },
+            #[cfg(feature = "xz")]
+            Compression::Xz { level: 6 },
+            #[cfg(feature = "bzip2")]
+            Compression::Bzip2 { level: 6 },
+            #[cfg(feature = "lz4")]
+            Compression::Lz4,
+        ];
+        let temp_dir = tempfile::tempdir().expect("Failed to create temp directory");
+        let input = "a log line that compresses well\n".repeat(100);
+
+        for compression in codecs {
+            let path = temp_dir
+                .path()
+                .join(format!("archive.{}", compression.extension()));
+            compression
+                .compress(&mut input.as_bytes(), File::create(&path).unwrap())
+                .expect("Failed to compress");
+
+            assert!(Compression::is_archive(&path));
+            assert_eq!(decompress(compression, File::open(&path).unwrap()), input);
+        }
+    }
+
+    #[test]
+    fn test_validate_levels() {
+        assert!(Compression::Gzip { level: 0 }.validate().is_ok());
+        assert!(Compression::Gzip { level: 10 }.validate().is_err());
+
+        #[cfg(feature = "zstd")]
+        {
+            assert!(Compression::Zstd { level: 22 }.validate().is_ok());
+            assert!(Compression::Zstd { level: 0 }.validate().is_err());
+            assert!(Compression::Zstd { level: 23 }.validate().is_err());
+        }
+        #[cfg(feature = "xz")]
+        {
+            assert!(Compression::Xz { level: 0 }.validate().is_ok());
+            assert!(Compression::Xz { level: 10 }.validate().is_err());
+        }
+        #[cfg(feature = "bzip2")]
+        {
+            assert!(Compression::Bzip2 { level: 9 }.validate().is_ok());
+            assert!(Compression::Bzip2 { level: 0 }.validate().is_err());
+        }
+    }
+
+    #[test]
+    fn test_archive_extensions() {
+        assert!(Compression::is_archive(Path::new("app.log.2024-01-01.zst")));
+        assert!(!Compression::is_archive(Path::new("app.log.2024-01-01")));
+        assert_eq!(
+            Compression::strip_archive_extension("app.log.2024-01-01.bz2"),
+            "app.log.2024-01-01"
+        );
+        assert_eq!(
+            Compression::strip_archive_extension("app.log.2024-01-01"),
+            "app.log.2024-01-01"
+        );
+    }
+}
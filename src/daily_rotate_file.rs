@@ -1,87 +1,250 @@
-use chrono::{DateTime, Local, Utc};
-use flate2::{write::GzEncoder, Compression};
-use logform::{Format, LogInfo};
-use std::fs::{create_dir_all, read_dir, File, OpenOptions};
+use crate::audit::{AuditKeep, AuditLog};
+use crate::clock::{Clock, SystemClock};
+use crate::compression::Compression;
+use crate::date_pattern;
+use crate::error::DailyRotateFileError;
+use crate::filename::{hostname, FileNameTemplate};
+use crate::frequency::Frequency;
+use crate::housekeeping::{Housekeeping, HousekeepingWorker, Job};
+use crate::retention::Retention;
+use crate::schedule::Schedule;
+use crate::size::parse_size;
+use crate::timezone::Timezone;
+use chrono::{DateTime, NaiveDateTime, NaiveTime, Utc};
+use logform::{json, Format, LogInfo};
+use std::collections::HashMap;
+use std::fs::{create_dir_all, File, OpenOptions};
 use std::io::{BufWriter, ErrorKind, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use winston_transport::Transport;
 
+const DEFAULT_COMPRESSION_BUFFER_SIZE: usize = 64 * 1024;
+
+/// The npm levels used by winston, from most to least severe.
+pub fn default_levels() -> HashMap<String, usize> {
+    HashMap::from([
+        ("error".to_string(), 0),
+        ("warn".to_string(), 1),
+        ("info".to_string(), 2),
+        ("http".to_string(), 3),
+        ("verbose".to_string(), 4),
+        ("debug".to_string(), 5),
+        ("silly".to_string(), 6),
+    ])
+}
+
+/// Receives write, rotation, compression and cleanup failures that cannot be returned to the caller.
+pub type ErrorHandler = Arc<dyn Fn(&DailyRotateFileError) + Send + Sync>;
+
+/// Receives notices about settings that work but are probably not what was meant.
+pub type WarningHandler = Arc<dyn Fn(&str) + Send + Sync>;
+
+/// Called with the path of a file the transport created, archived or removed.
+pub type FileHook = Arc<dyn Fn(&Path) + Send + Sync>;
+
+/// Called with the previous and the new active file after a rotation.
+pub type RotateHook = Arc<dyn Fn(&Path, &Path) + Send + Sync>;
+
 pub struct DailyRotateFileOptions {
     pub level: Option<String>,
+    pub levels: HashMap<String, usize>, // the npm levels unless custom ones were set
+    pub level_threshold: Option<usize>, // severity of `level` in `levels`, resolved by `build`
     pub format: Option<Arc<dyn Format<Input = LogInfo> + Send + Sync>>,
-    pub filename: PathBuf,
+    pub filename: PathBuf, // may contain a %DATE% placeholder, e.g. "app-%DATE%.log"
+    pub extension: Option<String>, // appended after the date and size counter
+    pub filename_variables: HashMap<String, String>, // values of the %NAME% tokens in `filename`
     pub date_pattern: String,
-    pub max_files: Option<u32>,
-    pub max_size: Option<u64>, // in bytes
+    pub frequency: Option<Frequency>, // rotate on these wall-clock periods instead of date_pattern changes
+    pub schedule: Option<Schedule>,   // rotate whenever this cron expression fires
+    pub dir_pattern: Option<String>, // date pattern for subdirectories of the log directory, e.g. "%Y/%m/%d"
+    pub max_files: Option<Retention>,
+    pub max_size: Option<u64>,       // in bytes
+    pub max_total_size: Option<u64>, // in bytes, across the active, rotated and archived files
     pub dirname: Option<PathBuf>,
-    pub zipped_archive: bool,
-    pub utc: bool,
+    pub compression: Option<Compression>, // archive rotated files with this codec
+    pub compression_buffer_size: usize,   // bytes read from the rotated file at a time
+    pub compress_after: u32,              // most recent rotated files left uncompressed
+    pub compress_after_age: Option<Duration>, // rotated files younger than this stay uncompressed
+    pub archive_dir: Option<PathBuf>,     // rotated and compressed files are moved here
+    pub archive_subdir: Option<String>, // date pattern for subfolders of `archive_dir`, e.g. "%Y/%m"
+    pub create_symlink: bool,           // keep a symlink in `dirname` pointing at the active file
+    pub symlink_name: String,
+    pub timezone: Timezone, // where periods start and file name dates are written
+    pub clock: Arc<dyn Clock>, // the system time unless a test clock was set
+    pub append: bool, // reopen the current period's file on startup instead of creating a sibling
+    pub on_error: Option<ErrorHandler>, // errors are dropped silently when unset
+    pub on_new: Option<FileHook>,
+    pub on_rotate: Option<RotateHook>,
+    pub on_archive: Option<FileHook>,
+    pub on_log_removed: Option<FileHook>,
+    pub audit_file: Option<PathBuf>, // JSON record of created files, used for `max_files` pruning
+    pub background_compression: bool, // compress and clean up on a worker thread after rotation
 }
 
 pub struct DailyRotateFile {
     file: Mutex<BufWriter<File>>,
-    options: DailyRotateFileOptions,
+    options: Arc<DailyRotateFileOptions>,
     last_rotation: Mutex<DateTime<Utc>>,
-    file_path: Mutex<PathBuf>,
+    next_scheduled: Mutex<Option<NaiveDateTime>>, // when `schedule` fires after `last_rotation`
+    file_path: Arc<Mutex<PathBuf>>,
+    housekeeping: Arc<Housekeeping>,
+    worker: Option<HousekeepingWorker>, // set when compression and cleanup run in the background
 }
 
 impl DailyRotateFile {
-    pub fn new(options: DailyRotateFileOptions) -> Self {
-        let current_date = if options.utc {
-            Utc::now()
+    pub fn new(options: DailyRotateFileOptions) -> Result<Self, DailyRotateFileError> {
+        let current_date = options.clock.now();
+
+        let (file, path) = Self::create_file(&options, &current_date, options.append)?;
+        if options.create_symlink {
+            Self::update_symlink(&options, &path)?;
+        }
+
+        let audit = match &options.audit_file {
+            Some(audit_file) => {
+                let keep = options.max_files.map(|retention| match retention {
+                    Retention::Count(amount) => AuditKeep {
+                        days: false,
+                        amount,
+                    },
+                    Retention::Days(amount) => AuditKeep { days: true, amount },
+                });
+                let mut audit = AuditLog::load(audit_file, keep)?;
+                if !audit.contains(&path) {
+                    audit.add(&path, current_date.timestamp_millis());
+                }
+                audit.save()?;
+                Some(Mutex::new(audit))
+            }
+            None => None,
+        };
+
+        if let Some(on_new) = &options.on_new {
+            on_new(&path);
+        }
+
+        let options = Arc::new(options);
+        let file_path = Arc::new(Mutex::new(path));
+        let housekeeping = Arc::new(Housekeeping {
+            options: Arc::clone(&options),
+            file_path: Arc::clone(&file_path),
+            audit,
+        });
+
+        let worker = if options.background_compression {
+            Some(HousekeepingWorker::spawn(Arc::clone(&housekeeping))?)
         } else {
-            Local::now().with_timezone(&Utc)
+            None
         };
 
-        let (file, path) =
-            Self::create_file(&options, &current_date).expect("Failed to create initial log file");
+        // Redo archives that a previous process left half-written
+        for source in housekeeping.recover_interrupted_archives() {
+            match &worker {
+                Some(worker) => worker.submit(Job::Compress(source)),
+                None => {
+                    housekeeping.archive(&source);
+                }
+            }
+        }
+
+        let next_scheduled = Self::next_scheduled(&options, &current_date);
 
-        DailyRotateFile {
+        Ok(DailyRotateFile {
             file: Mutex::new(BufWriter::new(file)),
             options,
             last_rotation: Mutex::new(current_date),
-            file_path: Mutex::new(path),
-        }
+            next_scheduled: Mutex::new(next_scheduled),
+            file_path,
+            housekeeping,
+            worker,
+        })
     }
 
     fn create_file(
         options: &DailyRotateFileOptions,
         date: &DateTime<Utc>,
+        append: bool,
     ) -> std::io::Result<(File, PathBuf)> {
-        let filename =
-            Self::get_filename(&options.filename, date, &options.date_pattern, options.utc);
-
         let log_dir = options.dirname.as_deref().unwrap_or_else(|| Path::new("."));
-        let full_path = log_dir.join(&filename);
 
-        let parent = full_path.parent().unwrap_or(log_dir);
-        create_dir_all(parent)?;
+        let full_path = log_dir.join(Self::get_filename(options, date, 0));
+        create_dir_all(full_path.parent().unwrap_or(log_dir))?;
 
-        Self::create_unique_file(log_dir, &filename)
+        let reuse_existing = if append { Some(options.max_size) } else { None };
+
+        // Counters only grow within a period, so a compressed or pruned file's slot is never
+        // reused and archives keep the name of the file they were made from
+        let first_counter = match Self::highest_counter(options, date) {
+            Some(highest)
+                if append
+                    && log_dir
+                        .join(Self::get_filename(options, date, highest))
+                        .is_file() =>
+            {
+                highest
+            }
+            Some(highest) => highest + 1,
+            None => 0,
+        };
+
+        Self::create_unique_file(
+            log_dir,
+            |counter| Self::get_filename(options, date, counter),
+            first_counter,
+            reuse_existing,
+        )
     }
 
-    fn create_unique_file(log_dir: &Path, filename: &Path) -> std::io::Result<(File, PathBuf)> {
-        let mut counter = 0;
+    /// The highest size-rotation counter used by a log file or archive of the period containing
+    /// `date`, or `None` if the period has no files yet.
+    fn highest_counter(options: &DailyRotateFileOptions, date: &DateTime<Utc>) -> Option<u32> {
+        let log_dir = options.dirname.as_deref().unwrap_or_else(|| Path::new("."));
+        let file_dir = log_dir.join(Self::get_filename(options, date, 0));
+        let mut dirs = vec![file_dir.parent().unwrap_or(log_dir).to_path_buf()];
+        if let Some(archive_dir) = &options.archive_dir {
+            dirs.push(match &options.archive_subdir {
+                Some(pattern) => archive_dir.join(Self::format_date(options, date, pattern)),
+                None => archive_dir.clone(),
+            });
+        }
+
+        let template = Self::file_name_template(options);
+        let date_str = Self::format_date(options, date, &options.date_pattern);
 
-        let base_name = filename
-            .file_stem()
-            .unwrap_or_else(|| std::ffi::OsStr::new("log"));
-        let ext = filename.extension().and_then(|e| e.to_str()).unwrap_or("");
+        dirs.iter()
+            .filter_map(|dir| std::fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| template.counter(entry.file_name().to_str()?, &date_str))
+            .max()
+    }
 
-        loop {
-            let new_filename = if counter == 0 {
-                filename.to_path_buf()
-            } else {
-                let mut unique_filename = filename.to_path_buf();
-                unique_filename.set_file_name(if ext.is_empty() {
-                    format!("{}_{}", base_name.to_string_lossy(), counter)
-                } else {
-                    format!("{}_{}.{}", base_name.to_string_lossy(), counter, ext)
-                });
+    /// Opens the first free file among `filename(first_counter)`, `filename(first_counter + 1)`,
+    /// ... in `log_dir`.
+    ///
+    /// With `reuse_existing` set to `Some(max_size)`, an existing candidate is reopened for
+    /// appending unless it has already reached `max_size`, in which case the next counter is tried.
+    fn create_unique_file<F: Fn(u32) -> PathBuf>(
+        log_dir: &Path,
+        filename: F,
+        first_counter: u32,
+        reuse_existing: Option<Option<u64>>,
+    ) -> std::io::Result<(File, PathBuf)> {
+        let mut counter = first_counter;
 
-                log_dir.join(unique_filename)
-            };
+        loop {
+            let new_filename = log_dir.join(filename(counter));
+
+            if let Some(max_size) = reuse_existing {
+                if let Ok(metadata) = std::fs::metadata(&new_filename) {
+                    if metadata.is_file() && max_size.is_none_or(|max| metadata.len() < max) {
+                        let file = OpenOptions::new().append(true).open(&new_filename)?;
+                        return Ok((file, new_filename));
+                    }
+                }
+            }
 
             match OpenOptions::new()
                 .write(true)
@@ -98,21 +261,99 @@ impl DailyRotateFile {
         }
     }
 
-    fn get_filename(base_path: &Path, date: &DateTime<Utc>, pattern: &str, utc: bool) -> PathBuf {
-        let date_str = if utc {
-            date.format(pattern).to_string()
-        } else {
-            date.with_timezone(&Local).format(pattern).to_string()
+    /// Atomically repoints the `symlink_name` link in the log directory at `target`.
+    fn update_symlink(options: &DailyRotateFileOptions, target: &Path) -> std::io::Result<()> {
+        // The directory files are created in, above any `dir_pattern` subdirectories
+        let link_dir = options
+            .dirname
+            .as_deref()
+            .unwrap_or_else(|| Path::new("."))
+            .join(options.filename.parent().unwrap_or_else(|| Path::new("")));
+        let link_dir = link_dir.as_path();
+        let link_path = link_dir.join(&options.symlink_name);
+
+        // A relative target keeps the link valid when the directory is mounted elsewhere
+        let link_target = match target.strip_prefix(link_dir) {
+            Ok(relative) => relative.to_path_buf(),
+            Err(_) => std::path::absolute(target)?,
         };
 
-        let mut filename = base_path.to_path_buf();
-        let original_filename = filename
+        // Build the new link beside the old one and rename it over, so readers never miss it
+        let tmp_path = link_dir.join(format!(
+            ".{}.{}.tmp",
+            options.symlink_name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_file(&tmp_path);
+
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&link_target, &tmp_path)?;
+        #[cfg(windows)]
+        std::os::windows::fs::symlink_file(&link_target, &tmp_path)?;
+
+        std::fs::rename(&tmp_path, &link_path).inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp_path);
+        })
+    }
+
+    /// The path of the log file for the period containing `date`, with the size-rotation
+    /// `counter` left out when it is 0.
+    fn get_filename(
+        options: &DailyRotateFileOptions,
+        date: &DateTime<Utc>,
+        counter: u32,
+    ) -> PathBuf {
+        let date_str = Self::format_date(options, date, &options.date_pattern);
+        let filename = Self::file_name_template(options).render(&date_str, counter);
+
+        match &options.dir_pattern {
+            Some(dir_pattern) => options
+                .filename
+                .with_file_name(Self::format_date(options, date, dir_pattern))
+                .join(filename),
+            None => options.filename.with_file_name(filename),
+        }
+    }
+
+    /// The template every log file name follows, built from the configured `filename`.
+    fn file_name_template(options: &DailyRotateFileOptions) -> FileNameTemplate {
+        let original_filename = options
+            .filename
             .file_name()
             .and_then(|f| f.to_str())
             .unwrap_or("log");
 
-        filename.set_file_name(format!("{}.{}", original_filename, date_str));
-        filename
+        FileNameTemplate::new(
+            original_filename,
+            options.extension.as_deref(),
+            &options.filename_variables,
+        )
+    }
+
+    /// `date` as wall-clock time in the configured timezone.
+    fn wall_clock(options: &DailyRotateFileOptions, date: &DateTime<Utc>) -> NaiveDateTime {
+        options.timezone.wall_clock(date)
+    }
+
+    /// The first time `schedule` fires after a rotation at `last_rotation`, as wall-clock time.
+    /// Computed once per rotation, since sparse schedules take many steps to search.
+    fn next_scheduled(
+        options: &DailyRotateFileOptions,
+        last_rotation: &DateTime<Utc>,
+    ) -> Option<NaiveDateTime> {
+        options
+            .schedule
+            .as_ref()?
+            .next_after(Self::wall_clock(options, last_rotation))
+    }
+
+    /// `date` formatted with `pattern` in the configured timezone.
+    fn format_date(
+        options: &DailyRotateFileOptions,
+        date: &DateTime<Utc>,
+        pattern: &str,
+    ) -> String {
+        options.timezone.format(date, pattern)
     }
 
     fn get_file_size(&self) -> u64 {
@@ -126,28 +367,40 @@ impl DailyRotateFile {
             .unwrap_or(0)
     }
 
+    /// Whether the next entry belongs in a new file.
+    ///
+    /// Time-based rotation follows the wall clock of the configured timezone. When DST ends and
+    /// the clock runs back, the repeated hour stays in the current file rather than reopening
+    /// an earlier period's name with a counter, unless `date_pattern` includes the offset
+    /// (`%z` or `%Z`) and so gives it names of its own. When DST starts, the skipped
+    /// periods get no file and boundaries inside the gap fire when the clock jumps.
     fn should_rotate(&self, new_entry_size: usize) -> bool {
-        let now = Utc::now();
-
-        let now_str = if self.options.utc {
-            now.format(&self.options.date_pattern).to_string()
-        } else {
-            now.with_timezone(&Local)
-                .format(&self.options.date_pattern)
-                .to_string()
-        };
-
-        let last_rotation = self.last_rotation.lock().unwrap();
-        let last_rotation_str = if self.options.utc {
-            last_rotation.format(&self.options.date_pattern).to_string()
-        } else {
-            last_rotation
-                .with_timezone(&Local)
-                .format(&self.options.date_pattern)
-                .to_string()
+        let now = self.options.clock.now();
+        let last_rotation = *self.last_rotation.lock().unwrap();
+        let now_wall = Self::wall_clock(&self.options, &now);
+        let last_wall = Self::wall_clock(&self.options, &last_rotation);
+
+        let period_changed = match (&self.options.schedule, self.options.frequency) {
+            (Some(_), _) => self
+                .next_scheduled
+                .lock()
+                .unwrap()
+                .is_some_and(|next| next <= now_wall),
+            (None, Some(frequency)) => {
+                frequency.period_start(now_wall) > frequency.period_start(last_wall)
+            }
+            (None, None) => {
+                let pattern = &self.options.date_pattern;
+                let names_offset = ["%z", "%Z"]
+                    .iter()
+                    .any(|specifier| pattern.contains(specifier));
+                (now_wall >= last_wall || names_offset)
+                    && Self::format_date(&self.options, &last_rotation, pattern)
+                        != Self::format_date(&self.options, &now, pattern)
+            }
         };
 
-        if last_rotation_str != now_str {
+        if period_changed {
             return true;
         }
 
@@ -157,8 +410,29 @@ impl DailyRotateFile {
             .unwrap_or(false)
     }
 
-    fn rotate(&self) {
-        let now = Utc::now();
+    /// When the next time-based rotation is due under `schedule` or `frequency`, or `None`
+    /// when rotation follows `date_pattern` changes or the schedule never fires again.
+    pub fn next_rotation(&self) -> Option<DateTime<Utc>> {
+        let next = match (&self.options.schedule, self.options.frequency) {
+            (Some(_), _) => (*self.next_scheduled.lock().unwrap())?,
+            (None, Some(frequency)) => {
+                let last_rotation = *self.last_rotation.lock().unwrap();
+                let start = frequency.period_start(Self::wall_clock(&self.options, &last_rotation));
+                let end = start + chrono::Duration::minutes(frequency.minutes() as i64);
+                // A period that does not divide the day is cut short at midnight
+                let midnight = start.date().succ_opt()?.and_time(NaiveTime::MIN);
+                end.min(midnight)
+            }
+            (None, None) => return None,
+        };
+
+        self.options.timezone.from_wall_clock(next)
+    }
+
+    /// Switches to a new log file. If the new file cannot be created, the current file stays
+    /// active and the error is returned.
+    fn rotate(&self) -> Result<(), DailyRotateFileError> {
+        let now = self.options.clock.now();
 
         if let Ok(mut file_guard) = self.file.lock() {
             let _ = file_guard.flush();
@@ -166,8 +440,7 @@ impl DailyRotateFile {
 
         let previous_file_path = self.file_path.lock().unwrap().clone();
 
-        let (new_file, new_path) =
-            Self::create_file(&self.options, &now).expect("Failed to rotate log file");
+        let (new_file, new_path) = Self::create_file(&self.options, &now, false)?;
 
         // Replace the existing file with the new one
         if let Ok(mut file_lock) = self.file.lock() {
@@ -175,182 +448,73 @@ impl DailyRotateFile {
         }
 
         if let Ok(mut path_lock) = self.file_path.lock() {
-            *path_lock = new_path;
-        }
-
-        if let Ok(mut last_rotation) = self.last_rotation.lock() {
-            *last_rotation = now;
+            *path_lock = new_path.clone();
         }
 
-        if self.options.zipped_archive {
-            if let Err(e) = Self::compress_file(&previous_file_path) {
-                eprintln!("Failed to compress log file: {}", e);
+        if self.options.create_symlink {
+            if let Err(e) = Self::update_symlink(&self.options, &new_path) {
+                self.housekeeping.report_error(e.into());
             }
         }
 
-        if let Some(max_files) = self.options.max_files {
-            if let Err(e) = self.cleanup_old_files(max_files) {
-                eprintln!("Failed to clean up old log files: {}", e);
-            }
+        if let Ok(mut last_rotation) = self.last_rotation.lock() {
+            *last_rotation = now;
         }
-    }
-
-    fn compress_file(file_path: &Path) -> std::io::Result<()> {
-        let mut counter = 0;
-
-        let base_name = file_path
-            .file_stem()
-            .unwrap_or_else(|| std::ffi::OsStr::new("compressed"));
-
-        let original_ext = file_path
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .unwrap_or("");
-
-        loop {
-            let attempt_path = if counter == 0 {
-                if original_ext.is_empty() {
-                    file_path.with_file_name(format!("{}.gz", base_name.to_string_lossy()))
-                } else {
-                    file_path.with_file_name(format!(
-                        "{}.{}.gz",
-                        base_name.to_string_lossy(),
-                        original_ext
-                    ))
-                }
-            } else {
-                let unique_filename = if original_ext.is_empty() {
-                    format!("{}_{}.gz", base_name.to_string_lossy(), counter)
-                } else {
-                    format!(
-                        "{}.{}_{}.gz",
-                        base_name.to_string_lossy(),
-                        original_ext,
-                        counter
-                    )
-                };
-
-                file_path.with_file_name(unique_filename)
-            };
-
-            match OpenOptions::new()
-                .write(true)
-                .create_new(true)
-                .open(&attempt_path)
-            {
-                Ok(gz_file) => {
-                    let input_file = File::open(file_path)?;
-                    let mut encoder = GzEncoder::new(gz_file, Compression::default());
-
-                    std::io::copy(&mut &input_file, &mut encoder)?;
-                    encoder.finish()?;
-
-                    std::fs::remove_file(file_path)?;
-
-                    return Ok(());
-                }
-                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
-                    counter += 1;
-                    continue;
-                }
-                Err(e) => return Err(e),
-            }
+        if let Ok(mut next_scheduled) = self.next_scheduled.lock() {
+            *next_scheduled = Self::next_scheduled(&self.options, &now);
         }
-    }
-
-    fn cleanup_old_files(&self, max_files: u32) -> std::io::Result<()> {
-        //println!("cleaning up");
-
-        let log_dir = self
-            .options
-            .dirname
-            .as_deref()
-            .or_else(|| self.options.filename.parent())
-            .unwrap_or_else(|| Path::new("."));
-
-        let base_name = self
-            .options
-            .filename
-            .file_name()
-            .and_then(|s| s.to_str())
-            .unwrap_or("log");
-
-        let mut log_files: Vec<PathBuf> = Vec::new();
-
-        // add all log files, zipped ones inclusive
-        for entry in read_dir(log_dir)? {
-            let entry = entry?;
-            let path = entry.path();
 
-            if path.is_file() {
-                let filename = path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+        self.housekeeping
+            .update_audit(|audit| audit.add(&new_path, now.timestamp_millis()));
 
-                //println!("Checking file: {}", filename);
-                //println!("Base name: {}", base_name);
-
-                // Check if it's one of our log files ("basename.date" and "basename_N.date" formats)
-                if filename.starts_with(&format!("{}.", base_name))
-                    || filename.starts_with(&format!("{}_", base_name))
-                {
-                    log_files.push(path);
-                }
-            }
+        if let Some(on_new) = &self.options.on_new {
+            on_new(&new_path);
+        }
+        if let Some(on_rotate) = &self.options.on_rotate {
+            on_rotate(&previous_file_path, &new_path);
         }
 
-        //println!("log files found: {:?}", log_files);
-
-        if log_files.len() <= max_files as usize {
-            return Ok(());
+        match &self.worker {
+            Some(worker) => worker.submit(Job::AfterRotation(previous_file_path)),
+            None => self.housekeeping.after_rotation(&previous_file_path),
         }
 
-        // Sort by modification time (newest first)
-        log_files.sort_by(|a, b| {
-            let a_time = a
-                .metadata()
-                .ok()
-                .and_then(|m| m.modified().ok())
-                .unwrap_or_else(|| std::time::SystemTime::UNIX_EPOCH);
+        Ok(())
+    }
 
-            let b_time = b
-                .metadata()
-                .ok()
-                .and_then(|m| m.modified().ok())
-                .unwrap_or_else(|| std::time::SystemTime::UNIX_EPOCH);
+    /// The minimum severity this transport writes, if one was configured.
+    pub fn get_level(&self) -> Option<&String> {
+        self.options.level.as_ref()
+    }
 
-            b_time.cmp(&a_time)
-        });
+    /// The format applied to entries before they are written, if one was configured.
+    pub fn get_format(&self) -> Option<&Arc<dyn Format<Input = LogInfo> + Send + Sync>> {
+        self.options.format.as_ref()
+    }
 
-        for file in &log_files {
-            println!("Detected log file: {}", file.display());
+    /// Whether an entry at `level` passes the configured level filter.
+    ///
+    /// Entries whose level is missing from the level map are dropped, as in winston.
+    pub fn is_level_enabled(&self, level: &str) -> bool {
+        match self.options.level_threshold {
+            Some(threshold) => self
+                .options
+                .levels
+                .get(level)
+                .is_some_and(|severity| *severity <= threshold),
+            None => true,
         }
+    }
 
-        // Keep only max_files
-        for old_file in log_files.iter().skip(max_files as usize) {
-            //println!("Deleting file: {}", old_file.display());
-
-            // don't delete active log file
-            let current_path = self.file_path.lock().map(|p| p.clone()).unwrap_or_default();
-            if old_file == &current_path {
-                continue;
-            }
-
-            if self.options.zipped_archive
-                && old_file.extension().and_then(|e| e.to_str()) != Some("gz")
-            {
-                // compress_file also deletes the original file
-                //let _ = Self::compress_file(old_file);
-                if let Err(e) = Self::compress_file(old_file) {
-                    eprintln!("Failed to compress old file {}: {}", old_file.display(), e);
-                }
-            } else {
-                //let _ = std::fs::remove_file(old_file);
-                if let Err(e) = std::fs::remove_file(old_file) {
-                    eprintln!("Failed to remove old file {}: {}", old_file.display(), e);
-                }
-            }
-        }
+    /// Runs `info` through the configured format (JSON when none is set) and returns the
+    /// line to write, or `None` if the format filtered the entry out.
+    fn format_entry(&self, info: LogInfo) -> Option<String> {
+        let formatted = match &self.options.format {
+            Some(format) => format.transform(info)?,
+            None => json().transform(info)?,
+        };
 
-        Ok(())
+        Some(format!("{}\n", formatted.message))
     }
 
     pub fn builder() -> DailyRotateFileBuilder {
@@ -360,29 +524,35 @@ impl DailyRotateFile {
 
 impl Transport<LogInfo> for DailyRotateFile {
     fn log(&self, info: LogInfo) {
-        let entry_size = format!("{}\n", info.message).len();
+        if !self.is_level_enabled(&info.level) {
+            return;
+        }
+
+        let entry = match self.format_entry(info) {
+            Some(entry) => entry,
+            None => return,
+        };
 
-        if self.should_rotate(entry_size) {
-            self.rotate();
+        if self.should_rotate(entry.len()) {
+            if let Err(e) = self.rotate() {
+                self.housekeeping.report_error(e);
+            }
         }
-        //println!("File size before: {}", self.get_file_size());
 
         let mut file = match self.file.lock() {
             Ok(f) => f,
             Err(_) => {
-                eprintln!("Failed to acquire file lock");
+                self.housekeeping
+                    .report_error(DailyRotateFileError::Io(std::io::Error::other(
+                        "log file lock poisoned",
+                    )));
                 return;
             }
         };
 
-        if let Err(e) = writeln!(file, "{}", info.message) {
-            eprintln!("Failed to write log: {}", e);
-            return;
+        if let Err(e) = file.write_all(entry.as_bytes()) {
+            self.housekeeping.report_error(e.into());
         }
-
-        //drop(file);
-
-        //println!("File size after: {}", self.get_file_size()); //deadlocks
     }
 
     fn log_batch(&self, infos: Vec<LogInfo>) {
@@ -390,32 +560,50 @@ impl Transport<LogInfo> for DailyRotateFile {
             return;
         }
 
-        // Calculate the total size of the batch to determine if rotation is needed before writing
-        let total_batch_size: usize = infos
-            .iter()
-            .map(|info| format!("{}\n", info.message).len())
-            .sum();
+        // Format the whole batch up front so rotation is decided on the bytes actually written
+        let entries: Vec<String> = infos
+            .into_iter()
+            .filter(|info| self.is_level_enabled(&info.level))
+            .filter_map(|info| self.format_entry(info))
+            .collect();
+
+        if entries.is_empty() {
+            return;
+        }
+
+        let total_batch_size: usize = entries.iter().map(|entry| entry.len()).sum();
 
         if self.should_rotate(total_batch_size) {
-            self.rotate();
+            if let Err(e) = self.rotate() {
+                self.housekeeping.report_error(e);
+            }
         }
 
         let mut file = match self.file.lock() {
             Ok(f) => f,
             Err(_) => {
-                eprintln!("Failed to acquire file lock for batch logging");
+                self.housekeeping
+                    .report_error(DailyRotateFileError::Io(std::io::Error::other(
+                        "log file lock poisoned",
+                    )));
                 return;
             }
         };
 
-        for info in infos {
-            if let Err(e) = writeln!(file, "{}", info.message) {
-                eprintln!("Failed to write log entry in batch: {}", e);
+        for entry in entries {
+            if let Err(e) = file.write_all(entry.as_bytes()) {
+                self.housekeeping.report_error(e.into());
             }
         }
     }
 
+    /// Flushes buffered entries and, with background compression, waits for pending archives
+    /// and cleanups to finish.
     fn flush(&self) -> Result<(), String> {
+        if let Some(worker) = &self.worker {
+            worker.wait_idle();
+        }
+
         let mut file = self.file.lock().unwrap();
         file.flush().map_err(|e| format!("Failed to flush: {}", e))
     }
@@ -423,28 +611,78 @@ impl Transport<LogInfo> for DailyRotateFile {
 
 pub struct DailyRotateFileBuilder {
     level: Option<String>,
+    levels: Option<HashMap<String, usize>>,
     format: Option<Arc<dyn Format<Input = LogInfo> + Send + Sync>>,
     filename: Option<PathBuf>,
+    extension: Option<String>,
+    filename_variables: HashMap<String, String>,
     date_pattern: String,
-    max_files: Option<u32>,
-    max_size: Option<u64>,
+    frequency: Option<String>,
+    schedule: Option<String>,
+    dir_pattern: Option<String>,
+    max_files: Option<String>,
+    max_size: Option<String>,
+    max_total_size: Option<String>,
     dirname: Option<PathBuf>,
-    zipped_archive: bool,
-    utc: bool,
+    compression: Option<Compression>,
+    compression_level: Option<u32>,
+    compression_buffer_size: Option<String>,
+    compress_after: u32,
+    compress_after_age: Option<Duration>,
+    archive_dir: Option<PathBuf>,
+    archive_subdir: Option<String>,
+    create_symlink: bool,
+    symlink_name: String,
+    timezone: Option<String>,
+    clock: Option<Arc<dyn Clock>>,
+    append: bool,
+    on_error: Option<ErrorHandler>,
+    on_warning: Option<WarningHandler>,
+    on_new: Option<FileHook>,
+    on_rotate: Option<RotateHook>,
+    on_archive: Option<FileHook>,
+    on_log_removed: Option<FileHook>,
+    audit_file: Option<PathBuf>,
+    background_compression: bool,
 }
 
 impl DailyRotateFileBuilder {
     pub fn new() -> Self {
         Self {
             level: None,
+            levels: None,
             format: None,
             filename: None,
+            extension: None,
+            filename_variables: HashMap::new(),
             date_pattern: String::from("%Y-%m-%d"),
+            frequency: None,
+            schedule: None,
+            dir_pattern: None,
             max_files: None,
             max_size: None,
+            max_total_size: None,
             dirname: None,
-            zipped_archive: false,
-            utc: false,
+            compression: None,
+            compression_level: None,
+            compression_buffer_size: None,
+            compress_after: 0,
+            compress_after_age: None,
+            archive_dir: None,
+            archive_subdir: None,
+            create_symlink: false,
+            symlink_name: String::from("current.log"),
+            timezone: None,
+            clock: None,
+            append: false,
+            on_error: None,
+            on_warning: None,
+            on_new: None,
+            on_rotate: None,
+            on_archive: None,
+            on_log_removed: None,
+            audit_file: None,
+            background_compression: false,
         }
     }
 
@@ -453,28 +691,90 @@ impl DailyRotateFileBuilder {
         self
     }
 
+    /// Replaces the npm levels with a custom severity map (lower is more severe).
+    pub fn levels(mut self, levels: HashMap<String, usize>) -> Self {
+        self.levels = Some(levels);
+        self
+    }
+
     pub fn format(mut self, format: Arc<dyn Format<Input = LogInfo> + Send + Sync>) -> Self {
         self.format = Some(format);
         self
     }
 
+    /// The log file path. A `%DATE%` placeholder is replaced by the formatted `date_pattern`
+    /// (`app-%DATE%.log`); without one, the date is appended (`app.log.2026-10-16`).
     pub fn filename<T: Into<PathBuf>>(mut self, filename: T) -> Self {
         self.filename = Some(filename.into());
         self
     }
 
+    /// An extension such as `".log"` appended after the date and the size-rotation counter,
+    /// e.g. `app-2026-10-16.1.log`.
+    pub fn extension<T: Into<String>>(mut self, extension: T) -> Self {
+        self.extension = Some(extension.into());
+        self
+    }
+
+    /// Replaces the `%NAME%` token in `filename` with `value`, e.g. to keep instances sharing a
+    /// volume apart. `%HOST%` and `%PID%` are available without registering them.
+    ///
+    /// Cleanup only covers files with this host's and the other variables' values, but files
+    /// with any `%PID%`, so retention still applies to the files of earlier runs.
+    pub fn filename_variable<K: Into<String>, V: ToString>(mut self, name: K, value: V) -> Self {
+        self.filename_variables
+            .insert(name.into(), value.to_string());
+        self
+    }
+
+    /// The strftime pattern for the date in file names, `"%Y-%m-%d"` by default. `build` rejects
+    /// invalid patterns and ones producing `:`, path separators or control characters in the
+    /// configured timezone, such as `%Z` without a named zone. Use `dir_pattern` to nest files.
     pub fn date_pattern<T: Into<String>>(mut self, pattern: T) -> Self {
         self.date_pattern = pattern.into();
         self
     }
 
-    pub fn max_files(mut self, count: u32) -> Self {
-        self.max_files = Some(count);
+    /// Rotates every `"15m"`, `"1h"` or `"daily"`, at boundaries aligned to the wall clock,
+    /// instead of whenever the formatted `date_pattern` changes.
+    pub fn frequency<T: ToString>(mut self, frequency: T) -> Self {
+        self.frequency = Some(frequency.to_string());
+        self
+    }
+
+    /// Rotates whenever the cron expression fires, e.g. `"0 2 * * *"` for 02:00 every day or
+    /// `"0 0 * * mon"` for Mondays only, evaluated in the configured timezone.
+    pub fn schedule<T: ToString>(mut self, expression: T) -> Self {
+        self.schedule = Some(expression.to_string());
+        self
+    }
+
+    /// Writes each period's files into a subdirectory of the log directory named by this date
+    /// pattern, e.g. `"%Y/%m/%d"` for `logs/2026/10/16/app.log.2026-10-16`. Cleanup walks these
+    /// directories and removes the ones it empties.
+    pub fn dir_pattern<T: Into<String>>(mut self, pattern: T) -> Self {
+        self.dir_pattern = Some(pattern.into());
+        self
+    }
+
+    /// How many files to keep: a count (`3`, `"10"`) or a number of days (`"14d"`).
+    /// Malformed values are rejected by `build`.
+    pub fn max_files<T: ToString>(mut self, max_files: T) -> Self {
+        self.max_files = Some(max_files.to_string());
+        self
+    }
+
+    /// Size at which the active file is rotated: bytes (`1024`) or a string like `"20m"`.
+    /// Malformed values are rejected by `build`.
+    pub fn max_size<T: ToString>(mut self, size: T) -> Self {
+        self.max_size = Some(size.to_string());
         self
     }
 
-    pub fn max_size(mut self, size: u64) -> Self {
-        self.max_size = Some(size);
+    /// Caps the combined size of all log files; the oldest ones are deleted after each rotation
+    /// until the total fits. The active file is never deleted. Accepts the same sizes as `max_size`.
+    pub fn max_total_size<T: ToString>(mut self, size: T) -> Self {
+        self.max_total_size = Some(size.to_string());
         self
     }
 
@@ -483,97 +783,643 @@ impl DailyRotateFileBuilder {
         self
     }
 
+    /// Gzips rotated files. Shorthand for `compression(Compression::default())`.
     pub fn zipped_archive(mut self, zipped: bool) -> Self {
-        self.zipped_archive = zipped;
+        self.compression = zipped.then(Compression::default);
         self
     }
 
-    pub fn utc(mut self, utc: bool) -> Self {
-        self.utc = utc;
+    /// Archives rotated files with `compression`; codecs other than gzip need their cargo feature.
+    /// Levels outside the codec's range are rejected by `build`.
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
         self
     }
 
-    pub fn build(self) -> Result<DailyRotateFile, String> {
-        let filename = self.filename.ok_or("Filename is required")?;
-
-        let options = DailyRotateFileOptions {
-            level: self.level,
-            format: self.format,
-            filename,
-            date_pattern: self.date_pattern,
-            max_files: self.max_files,
-            max_size: self.max_size,
-            dirname: self.dirname,
-            zipped_archive: self.zipped_archive,
-            utc: self.utc,
-        };
+    /// gzip level from 0 (fastest, no compression) to 9 (smallest), trading CPU for disk.
+    /// Requires gzip compression; out-of-range levels are rejected by `build`.
+    pub fn compression_level(mut self, level: u32) -> Self {
+        self.compression_level = Some(level);
+        self
+    }
 
-        Ok(DailyRotateFile::new(options))
+    /// How much of the rotated file is read at a time while compressing, in bytes or as a size
+    /// string like `"1m"`. Defaults to 64 KiB.
+    pub fn compression_buffer_size<T: ToString>(mut self, size: T) -> Self {
+        self.compression_buffer_size = Some(size.to_string());
+        self
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use chrono::Local;
-    use std::fs;
-    use tempfile::TempDir;
+    /// Leaves the `count` most recently rotated files uncompressed so tools still tailing or
+    /// shipping them keep their handle; older files are compressed on a later rotation.
+    pub fn compress_after(mut self, count: u32) -> Self {
+        self.compress_after = count;
+        self
+    }
 
-    fn setup_temp_dir() -> TempDir {
-        let project_root = std::env::current_dir().expect("Failed to get current directory");
-        TempDir::new_in(&project_root).expect("Failed to create temp directory in project folder")
+    /// Leaves rotated files uncompressed until they are at least `age` old.
+    pub fn compress_after_age(mut self, age: Duration) -> Self {
+        self.compress_after_age = Some(age);
+        self
     }
 
-    fn create_test_transport(temp_dir: &TempDir) -> DailyRotateFile {
-        let log_path = temp_dir.path().join("test.log");
-        DailyRotateFile::builder()
-            .filename(&log_path)
-            .date_pattern("%Y-%m-%d")
-            .max_files(3)
-            .max_size(1024) // 1KB
-            .build()
-            .expect("Failed to create transport")
+    /// Moves rotated files, compressed or not, out of `dirname` into `path`. Retention cleanup
+    /// covers this directory as well.
+    pub fn archive_dir<T: Into<PathBuf>>(mut self, path: T) -> Self {
+        self.archive_dir = Some(path.into());
+        self
     }
 
-    #[test]
-    fn test_basic_logging() {
-        let temp_dir = setup_temp_dir();
-        let transport = create_test_transport(&temp_dir);
+    /// Files archived into `archive_dir` into date-based subfolders, e.g. `"%Y/%m"` for
+    /// `archive/2026/10/`, expanded for the period each file belongs to.
+    pub fn archive_subdir<T: Into<String>>(mut self, pattern: T) -> Self {
+        self.archive_subdir = Some(pattern.into());
+        self
+    }
 
-        let log_info = LogInfo {
-            level: "info".to_string(),
-            message: "Test message".to_string(),
-            meta: Default::default(),
-        };
+    /// Keeps a symlink in `dirname` pointing at the active file, so `tail -F` and log shippers
+    /// can follow one stable name across rotations.
+    pub fn create_symlink(mut self, create: bool) -> Self {
+        self.create_symlink = create;
+        self
+    }
 
-        transport.log(log_info);
-        transport.flush().expect("Failed to flush");
+    /// The name of the symlink kept by `create_symlink`. Defaults to `current.log`.
+    pub fn symlink_name<T: Into<String>>(mut self, name: T) -> Self {
+        self.symlink_name = name.into();
+        self
+    }
 
-        // Check if log file exists and contains the message
-        let date_str = Local::now().format("%Y-%m-%d").to_string();
-        let log_file = temp_dir.path().join(format!("test.log.{}", date_str));
-        let contents = fs::read_to_string(log_file).expect("Failed to read log file");
-        assert!(contents.contains("Test message"));
+    /// Shorthand for `timezone("UTC")`, or back to the host's local time with `false`.
+    pub fn utc(mut self, utc: bool) -> Self {
+        self.timezone = utc.then(|| "UTC".to_string());
+        self
     }
 
-    #[test]
-    fn test_date_based_rotation() {
-        let temp_dir = setup_temp_dir();
-        let log_path = temp_dir.path().join("test.log");
-        let transport = DailyRotateFile::builder()
-            .filename(log_path)
-            .date_pattern("%Y-%m-%d_%H-%M-%S")
-            .build()
-            .expect("Failed to create transport");
+    /// The timezone for period boundaries, file name dates and retention: `"local"` (the
+    /// default), `"UTC"`, a fixed offset like `"+05:30"` or, with the `chrono-tz` feature, an
+    /// IANA name like `"America/New_York"`.
+    pub fn timezone<T: ToString>(mut self, timezone: T) -> Self {
+        self.timezone = Some(timezone.to_string());
+        self
+    }
 
-        transport.log(LogInfo {
-            level: "info".to_string(),
-            message: "log entry 1".to_string(),
-            meta: Default::default(),
-        });
+    /// Replaces the system time used for rotation and retention, e.g. with a
+    /// [`ManualClock`](crate::ManualClock) in tests.
+    pub fn clock<C: Clock + 'static>(mut self, clock: Arc<C>) -> Self {
+        self.clock = Some(clock);
+        self
+    }
 
-        // Simulate date change
-        std::thread::sleep(std::time::Duration::from_secs(1));
+    /// Reopens today's existing file on startup instead of starting a new `_N` sibling.
+    pub fn append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// Registers a callback for failures that happen while logging, e.g. a full disk or a
+    /// failed compression. Without one, such failures are silently dropped.
+    pub fn on_error<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&DailyRotateFileError) + Send + Sync + 'static,
+    {
+        self.on_error = Some(Arc::new(handler));
+        self
+    }
+
+    /// Registers a callback for warnings about valid but likely unintended settings, e.g. a
+    /// `date_pattern` finer than the `frequency`. Without one, warnings are silently dropped.
+    pub fn on_warning<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.on_warning = Some(Arc::new(handler));
+        self
+    }
+
+    /// Called whenever a log file is opened for writing, at startup and after each rotation.
+    pub fn on_new<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Path) + Send + Sync + 'static,
+    {
+        self.on_new = Some(Arc::new(hook));
+        self
+    }
+
+    /// Called after each rotation with the previous and the new file.
+    pub fn on_rotate<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Path, &Path) + Send + Sync + 'static,
+    {
+        self.on_rotate = Some(Arc::new(hook));
+        self
+    }
+
+    /// Called with the path of each archived file: compressed, or moved into `archive_dir`.
+    pub fn on_archive<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Path) + Send + Sync + 'static,
+    {
+        self.on_archive = Some(Arc::new(hook));
+        self
+    }
+
+    /// Called with the path of each file deleted by retention cleanup.
+    pub fn on_log_removed<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Path) + Send + Sync + 'static,
+    {
+        self.on_log_removed = Some(Arc::new(hook));
+        self
+    }
+
+    /// Keeps a winston-daily-rotate-file compatible JSON audit of every log file at `path`.
+    /// When set, `max_files` prunes the files listed there instead of scanning the directory.
+    pub fn audit_file<T: Into<PathBuf>>(mut self, path: T) -> Self {
+        self.audit_file = Some(path.into());
+        self
+    }
+
+    /// Hands compression and retention cleanup to a worker thread so the entry that triggers a
+    /// rotation is not held up by compressing the previous file. `flush` waits for queued work,
+    /// and dropping the transport finishes it before returning.
+    pub fn background_compression(mut self, background: bool) -> Self {
+        self.background_compression = background;
+        self
+    }
+
+    pub fn build(self) -> Result<DailyRotateFile, DailyRotateFileError> {
+        let filename = self
+            .filename
+            .ok_or_else(|| DailyRotateFileError::InvalidConfig("Filename is required".into()))?;
+
+        let levels = self.levels.unwrap_or_else(default_levels);
+        let level_threshold = match &self.level {
+            Some(level) => match levels.get(level) {
+                Some(severity) => Some(*severity),
+                None => {
+                    return Err(DailyRotateFileError::InvalidConfig(format!(
+                        "Unknown level '{}'",
+                        level
+                    )))
+                }
+            },
+            None => None,
+        };
+
+        let max_files = self
+            .max_files
+            .as_deref()
+            .map(str::parse::<Retention>)
+            .transpose()?;
+
+        let frequency = self
+            .frequency
+            .as_deref()
+            .map(str::parse::<Frequency>)
+            .transpose()?;
+
+        let schedule = self
+            .schedule
+            .as_deref()
+            .map(str::parse::<Schedule>)
+            .transpose()?;
+
+        if frequency.is_some() && schedule.is_some() {
+            return Err(DailyRotateFileError::InvalidConfig(
+                "frequency and schedule cannot be combined".into(),
+            ));
+        }
+
+        let timezone = self
+            .timezone
+            .as_deref()
+            .map(str::parse::<Timezone>)
+            .transpose()?
+            .unwrap_or_default();
+
+        date_pattern::validate(&self.date_pattern, &timezone, false)?;
+        for pattern in self.dir_pattern.iter().chain(&self.archive_subdir) {
+            date_pattern::validate(pattern, &timezone, true)?;
+        }
+
+        // Not fatal: files are still rotated per frequency, but named more precisely than that
+        if let (Some(on_warning), Some(frequency), Some(resolution)) = (
+            &self.on_warning,
+            frequency,
+            date_pattern::resolution_minutes(&self.date_pattern),
+        ) {
+            if resolution < frequency.minutes() {
+                on_warning(&format!(
+                    "date_pattern '{}' is finer than the {} frequency",
+                    self.date_pattern, frequency
+                ));
+            }
+        }
+
+        let max_size = self.max_size.as_deref().map(parse_size).transpose()?;
+        let max_total_size = self.max_total_size.as_deref().map(parse_size).transpose()?;
+
+        let mut compression = self.compression;
+        if let Some(level) = self.compression_level {
+            match compression.as_mut() {
+                Some(Compression::Gzip { level: gzip_level }) if level <= 9 => *gzip_level = level,
+                Some(Compression::Gzip { .. }) => {
+                    return Err(DailyRotateFileError::InvalidConfig(format!(
+                        "compression_level must be between 0 and 9, got {}",
+                        level
+                    )))
+                }
+                _ => {
+                    return Err(DailyRotateFileError::InvalidConfig(
+                        "compression_level requires gzip compression".into(),
+                    ))
+                }
+            }
+        }
+
+        if let Some(compression) = &compression {
+            compression.validate()?;
+        }
+
+        let compression_buffer_size = match self.compression_buffer_size.as_deref() {
+            Some(size) => match usize::try_from(parse_size(size)?) {
+                Ok(size) if size > 0 => size,
+                _ => {
+                    return Err(DailyRotateFileError::InvalidConfig(format!(
+                        "Invalid compression_buffer_size '{}'",
+                        size
+                    )))
+                }
+            },
+            None => DEFAULT_COMPRESSION_BUFFER_SIZE,
+        };
+
+        let mut filename_variables = self.filename_variables;
+        for (name, value) in &filename_variables {
+            if name.is_empty() || name == "DATE" || name.contains('%') {
+                return Err(DailyRotateFileError::InvalidConfig(format!(
+                    "Invalid filename variable name '{}'",
+                    name
+                )));
+            }
+            if value.contains(['/', '\\', '%']) {
+                return Err(DailyRotateFileError::InvalidConfig(format!(
+                    "Invalid value '{}' for filename variable '{}'",
+                    value, name
+                )));
+            }
+        }
+        filename_variables
+            .entry("HOST".to_string())
+            .or_insert_with(hostname);
+        filename_variables
+            .entry("PID".to_string())
+            .or_insert_with(|| std::process::id().to_string());
+
+        if self.create_symlink
+            && Path::new(&self.symlink_name).file_name() != Some(self.symlink_name.as_ref())
+        {
+            return Err(DailyRotateFileError::InvalidConfig(format!(
+                "Invalid symlink_name '{}': expected a plain file name",
+                self.symlink_name
+            )));
+        }
+
+        if self.archive_subdir.is_some() && self.archive_dir.is_none() {
+            return Err(DailyRotateFileError::InvalidConfig(
+                "archive_subdir requires archive_dir".into(),
+            ));
+        }
+
+        let options = DailyRotateFileOptions {
+            level: self.level,
+            levels,
+            level_threshold,
+            format: self.format,
+            filename,
+            extension: self.extension,
+            filename_variables,
+            date_pattern: self.date_pattern,
+            frequency,
+            schedule,
+            dir_pattern: self.dir_pattern,
+            max_files,
+            max_size,
+            max_total_size,
+            dirname: self.dirname,
+            compression,
+            compression_buffer_size,
+            compress_after: self.compress_after,
+            compress_after_age: self.compress_after_age,
+            archive_dir: self.archive_dir,
+            archive_subdir: self.archive_subdir,
+            create_symlink: self.create_symlink,
+            symlink_name: self.symlink_name,
+            timezone,
+            clock: self.clock.unwrap_or_else(|| Arc::new(SystemClock)),
+            append: self.append,
+            on_error: self.on_error,
+            on_new: self.on_new,
+            on_rotate: self.on_rotate,
+            on_archive: self.on_archive,
+            on_log_removed: self.on_log_removed,
+            audit_file: self.audit_file,
+            background_compression: self.background_compression,
+        };
+
+        DailyRotateFile::new(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use chrono::Local;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn setup_temp_dir() -> TempDir {
+        let project_root = std::env::current_dir().expect("Failed to get current directory");
+        TempDir::new_in(&project_root).expect("Failed to create temp directory in project folder")
+    }
+
+    /// Writes only the message, so tests can reason about exact byte counts.
+    fn message_only() -> Arc<dyn Format<Input = LogInfo> + Send + Sync> {
+        Arc::new(logform::printf(|info| info.message.clone()))
+    }
+
+    fn create_test_transport(temp_dir: &TempDir) -> DailyRotateFile {
+        let log_path = temp_dir.path().join("test.log");
+        DailyRotateFile::builder()
+            .filename(&log_path)
+            .date_pattern("%Y-%m-%d")
+            .max_files(3)
+            .max_size(1024) // 1KB
+            .build()
+            .expect("Failed to create transport")
+    }
+
+    #[test]
+    fn test_basic_logging() {
+        let temp_dir = setup_temp_dir();
+        let transport = create_test_transport(&temp_dir);
+
+        let log_info = LogInfo {
+            level: "info".to_string(),
+            message: "Test message".to_string(),
+            meta: Default::default(),
+        };
+
+        transport.log(log_info);
+        transport.flush().expect("Failed to flush");
+
+        // Check if log file exists and contains the message
+        let date_str = Local::now().format("%Y-%m-%d").to_string();
+        let log_file = temp_dir.path().join(format!("test.log.{}", date_str));
+        let contents = fs::read_to_string(log_file).expect("Failed to read log file");
+        assert!(contents.contains("Test message"));
+    }
+
+    #[test]
+    fn test_default_format_writes_json() {
+        let temp_dir = setup_temp_dir();
+        let transport = create_test_transport(&temp_dir);
+
+        transport.log(LogInfo::new("warn", "Disk almost full").with_meta("usage", 93));
+        transport.flush().expect("Failed to flush");
+
+        let date_str = Local::now().format("%Y-%m-%d").to_string();
+        let log_file = temp_dir.path().join(format!("test.log.{}", date_str));
+        let contents = fs::read_to_string(log_file).expect("Failed to read log file");
+        let line: serde_json::Value =
+            serde_json::from_str(contents.trim_end()).expect("Expected a JSON line");
+
+        assert_eq!(line["level"], "warn");
+        assert_eq!(line["message"], "Disk almost full");
+        assert_eq!(line["usage"], 93);
+    }
+
+    #[test]
+    fn test_custom_format_is_applied() {
+        let temp_dir = setup_temp_dir();
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(Arc::new(logform::printf(|info| {
+                format!("[{}] {}", info.level.to_uppercase(), info.message)
+            })))
+            .build()
+            .expect("Failed to create transport");
+
+        transport.log_batch(vec![
+            LogInfo::new("info", "first"),
+            LogInfo::new("error", "second"),
+        ]);
+        transport.flush().expect("Failed to flush");
+
+        let date_str = Local::now().format("%Y-%m-%d").to_string();
+        let log_file = temp_dir.path().join(format!("test.log.{}", date_str));
+        let contents = fs::read_to_string(log_file).expect("Failed to read log file");
+        assert_eq!(contents, "[INFO] first\n[ERROR] second\n");
+    }
+
+    #[test]
+    fn test_level_filtering() {
+        let temp_dir = setup_temp_dir();
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .level("warn")
+            .build()
+            .expect("Failed to create transport");
+
+        assert_eq!(transport.get_level(), Some(&"warn".to_string()));
+        assert!(transport.is_level_enabled("error"));
+        assert!(!transport.is_level_enabled("info"));
+
+        transport.log(LogInfo::new("error", "kept error"));
+        transport.log(LogInfo::new("info", "dropped info"));
+        transport.log_batch(vec![
+            LogInfo::new("debug", "dropped debug"),
+            LogInfo::new("warn", "kept warn"),
+        ]);
+        transport.flush().expect("Failed to flush");
+
+        let date_str = Local::now().format("%Y-%m-%d").to_string();
+        let log_file = temp_dir.path().join(format!("test.log.{}", date_str));
+        let contents = fs::read_to_string(log_file).expect("Failed to read log file");
+        assert_eq!(contents, "kept error\nkept warn\n");
+    }
+
+    #[test]
+    fn test_custom_levels() {
+        let temp_dir = setup_temp_dir();
+        let levels = HashMap::from([
+            ("fatal".to_string(), 0),
+            ("audit".to_string(), 1),
+            ("trace".to_string(), 2),
+        ]);
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .levels(levels.clone())
+            .level("audit")
+            .build()
+            .expect("Failed to create transport");
+
+        assert!(transport.is_level_enabled("fatal"));
+        assert!(transport.is_level_enabled("audit"));
+        assert!(!transport.is_level_enabled("trace"));
+        assert!(!transport.is_level_enabled("info"));
+
+        let result = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("other.log"))
+            .levels(levels)
+            .level("info")
+            .build();
+        assert!(
+            matches!(result, Err(DailyRotateFileError::InvalidConfig(_))),
+            "Expected unknown level to be rejected"
+        );
+    }
+
+    #[test]
+    fn test_append_reopens_existing_file() {
+        let temp_dir = setup_temp_dir();
+        let build = || {
+            DailyRotateFile::builder()
+                .filename(temp_dir.path().join("test.log"))
+                .format(message_only())
+                .append(true)
+                .build()
+                .expect("Failed to create transport")
+        };
+
+        let transport = build();
+        transport.log(LogInfo::new("info", "before restart"));
+        transport.flush().expect("Failed to flush");
+        drop(transport);
+
+        let transport = build();
+        transport.log(LogInfo::new("info", "after restart"));
+        transport.flush().expect("Failed to flush");
+
+        let files: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .collect();
+        assert_eq!(files.len(), 1, "Expected the existing file to be reused");
+
+        let contents = fs::read_to_string(files[0].path()).expect("Failed to read log file");
+        assert_eq!(contents, "before restart\nafter restart\n");
+    }
+
+    #[test]
+    fn test_append_skips_full_file() {
+        let temp_dir = setup_temp_dir();
+        let date_str = Local::now().format("%Y-%m-%d").to_string();
+        let existing = temp_dir.path().join(format!("test.log.{}", date_str));
+        fs::write(&existing, "x".repeat(100)).expect("Failed to seed log file");
+
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .max_size(100)
+            .append(true)
+            .build()
+            .expect("Failed to create transport");
+        transport.log(LogInfo::new("info", "fresh"));
+        transport.flush().expect("Failed to flush");
+
+        assert_eq!(fs::read_to_string(&existing).unwrap().len(), 100);
+        let next = temp_dir.path().join(format!("test.log_1.{}", date_str));
+        assert_eq!(fs::read_to_string(next).unwrap(), "fresh\n");
+    }
+
+    #[test]
+    fn test_build_reports_io_error() {
+        let temp_dir = setup_temp_dir();
+        let blocker = temp_dir.path().join("not_a_dir");
+        fs::write(&blocker, "").expect("Failed to create blocking file");
+
+        let result = DailyRotateFile::builder()
+            .filename(blocker.join("test.log"))
+            .build();
+
+        assert!(matches!(result, Err(DailyRotateFileError::Io(_))));
+    }
+
+    #[test]
+    fn test_failed_rotation_keeps_previous_file() {
+        let temp_dir = setup_temp_dir();
+        let log_dir = temp_dir.path().join("logs");
+        let transport = DailyRotateFile::builder()
+            .filename(log_dir.join("test.log"))
+            .format(message_only())
+            .max_size(10)
+            .build()
+            .expect("Failed to create transport");
+        let active = transport.file_path.lock().unwrap().clone();
+
+        // Make the directory unusable so the next rotation cannot create a file
+        fs::remove_dir_all(&log_dir).expect("Failed to remove log directory");
+        fs::write(&log_dir, "").expect("Failed to create blocking file");
+
+        transport.log(LogInfo::new("info", "still written somewhere"));
+        transport.log(LogInfo::new("info", "and again"));
+        transport.flush().expect("Failed to flush");
+
+        assert_eq!(*transport.file_path.lock().unwrap(), active);
+    }
+
+    #[test]
+    fn test_on_error_receives_failures() {
+        let temp_dir = setup_temp_dir();
+        let log_dir = temp_dir.path().join("logs");
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&errors);
+        let transport = DailyRotateFile::builder()
+            .filename(log_dir.join("test.log"))
+            .format(message_only())
+            .max_size(10)
+            .on_error(move |e| sink.lock().unwrap().push(e.to_string()))
+            .build()
+            .expect("Failed to create transport");
+
+        fs::remove_dir_all(&log_dir).expect("Failed to remove log directory");
+        fs::write(&log_dir, "").expect("Failed to create blocking file");
+
+        transport.log(LogInfo::new("info", "triggers a failed rotation"));
+
+        let errors = errors.lock().unwrap();
+        assert_eq!(
+            errors.len(),
+            1,
+            "Expected the rotation failure to be reported"
+        );
+        assert!(errors[0].starts_with("I/O error"));
+    }
+
+    #[test]
+    fn test_date_based_rotation() {
+        let temp_dir = setup_temp_dir();
+        let log_path = temp_dir.path().join("test.log");
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let transport = DailyRotateFile::builder()
+            .filename(log_path)
+            .date_pattern("%Y-%m-%d_%H-%M-%S")
+            .clock(Arc::clone(&clock))
+            .build()
+            .expect("Failed to create transport");
+
+        transport.log(LogInfo {
+            level: "info".to_string(),
+            message: "log entry 1".to_string(),
+            meta: Default::default(),
+        });
+
+        // Simulate date change
+        clock.advance(chrono::Duration::seconds(1));
 
         transport.log(LogInfo {
             level: "info".to_string(),
@@ -596,6 +1442,7 @@ mod tests {
         let transport = DailyRotateFile::builder()
             .filename(temp_dir.path().join("test.log"))
             //.filename("logs/test.log")
+            .format(message_only())
             .max_size(100)
             .build()
             .expect("Failed to create transport");
@@ -634,6 +1481,7 @@ mod tests {
         let transport = DailyRotateFile::builder()
             .filename(temp_dir.path().join("test.log"))
             //.filename("logs/test.log")
+            .format(message_only())
             .max_size(80) // Small size to force rotation `Test message x` plus new line is 15 bytes each * 5 = 75 + 5 buffer
             .zipped_archive(true)
             .build()
@@ -679,35 +1527,1252 @@ mod tests {
     }
 
     #[test]
-    fn test_max_files_cleanup() {
+    fn test_lifecycle_hooks() {
         let temp_dir = setup_temp_dir();
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let (new_events, rotate_events, archive_events) = (
+            Arc::clone(&events),
+            Arc::clone(&events),
+            Arc::clone(&events),
+        );
         let transport = DailyRotateFile::builder()
             .filename(temp_dir.path().join("test.log"))
-            //.filename("logs/test.log")
-            .date_pattern("%Y-%m-%d_%H-%M-%S")
-            .max_files(2)
+            .format(message_only())
+            .max_size(10)
+            .zipped_archive(true)
+            .on_new(move |path| new_events.lock().unwrap().push(("new", path.to_path_buf())))
+            .on_rotate(move |old, _new| {
+                rotate_events
+                    .lock()
+                    .unwrap()
+                    .push(("rotate", old.to_path_buf()))
+            })
+            .on_archive(move |path| {
+                archive_events
+                    .lock()
+                    .unwrap()
+                    .push(("archive", path.to_path_buf()))
+            })
             .build()
             .expect("Failed to create transport");
 
-        for i in 0..5 {
-            transport.log(LogInfo {
-                level: "info".to_string(),
-                message: format!("Message {}", i),
+        let first = transport.file_path.lock().unwrap().clone();
+        transport.log(LogInfo::new("info", "long enough to rotate"));
+
+        let events = events.lock().unwrap();
+        let kinds: Vec<_> = events.iter().map(|(kind, _)| *kind).collect();
+        assert_eq!(kinds, ["new", "new", "rotate", "archive"]);
+        assert_eq!(events[0].1, first);
+        assert_eq!(events[2].1, first);
+        assert!(events[3].1.exists(), "Expected the archive to exist");
+    }
+
+    #[test]
+    fn test_log_removed_hook() {
+        let temp_dir = setup_temp_dir();
+        let removed = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&removed);
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .max_size(10)
+            .max_files(1)
+            .on_log_removed(move |path| sink.lock().unwrap().push(path.to_path_buf()))
+            .build()
+            .expect("Failed to create transport");
+
+        for i in 0..3 {
+            transport.log(LogInfo::new("info", format!("message number {}", i)));
+        }
+
+        let removed = removed.lock().unwrap();
+        assert!(
+            !removed.is_empty(),
+            "Expected cleanup to report removed files"
+        );
+        assert!(removed.iter().all(|path| !path.exists()));
+    }
+
+    #[test]
+    fn test_audit_file_drives_cleanup() {
+        let temp_dir = setup_temp_dir();
+        let audit_path = temp_dir.path().join("audit").join("test-audit.json");
+
+        // A file matching the naming scheme that the transport did not create
+        let date_str = Local::now().format("%Y-%m-%d").to_string();
+        let foreign = temp_dir.path().join(format!("test.log_99.{}", date_str));
+        fs::write(&foreign, "not ours").expect("Failed to create foreign file");
+
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .max_size(10)
+            .max_files(2)
+            .audit_file(&audit_path)
+            .build()
+            .expect("Failed to create transport");
+
+        for i in 0..4 {
+            transport.log(LogInfo::new("info", format!("message number {}", i)));
+        }
+        transport.flush().expect("Failed to flush");
+
+        let audit: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&audit_path).unwrap()).unwrap();
+        let names: Vec<_> = audit["files"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| PathBuf::from(entry["name"].as_str().unwrap()))
+            .collect();
+
+        assert_eq!(names.len(), 2, "Expected the audit to track 2 files");
+        assert!(names.iter().all(|path| path.exists()));
+        assert!(names.contains(&transport.file_path.lock().unwrap().clone()));
+        assert_eq!(audit["keep"]["amount"], 2);
+        assert!(
+            foreign.exists(),
+            "Files outside the audit must not be pruned"
+        );
+    }
+
+    #[test]
+    fn test_audit_file_survives_restart() {
+        let temp_dir = setup_temp_dir();
+        let audit_path = temp_dir.path().join("test-audit.json");
+        let build = || {
+            DailyRotateFile::builder()
+                .filename(temp_dir.path().join("test.log"))
+                .audit_file(&audit_path)
+                .append(true)
+                .build()
+                .expect("Failed to create transport")
+        };
+
+        drop(build());
+        drop(build());
+
+        let audit: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&audit_path).unwrap()).unwrap();
+        assert_eq!(
+            audit["files"].as_array().unwrap().len(),
+            1,
+            "Reopening the same file must not duplicate its audit entry"
+        );
+    }
+
+    #[test]
+    fn test_max_files_in_days() {
+        let temp_dir = setup_temp_dir();
+        let old_plain = temp_dir.path().join("test.log.2020-01-01");
+        let old_zipped = temp_dir.path().join("test.log_1.2020-01-02.gz");
+        let yesterday = (Local::now() - chrono::Duration::days(1))
+            .format("%Y-%m-%d")
+            .to_string();
+        let recent = temp_dir.path().join(format!("test.log.{}", yesterday));
+        for path in [&old_plain, &old_zipped, &recent] {
+            fs::write(path, "old entry").expect("Failed to seed log file");
+        }
+
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .max_size(10)
+            .max_files("7d")
+            .build()
+            .expect("Failed to create transport");
+        transport.log(LogInfo::new("info", "triggers a rotation"));
+
+        assert!(!old_plain.exists(), "Expected the 2020 file to be deleted");
+        assert!(
+            !old_zipped.exists(),
+            "Expected the 2020 archive to be deleted"
+        );
+        assert!(recent.exists(), "Expected yesterday's file to be kept");
+    }
+
+    #[test]
+    fn test_invalid_max_files_is_rejected() {
+        let temp_dir = setup_temp_dir();
+        let result = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .max_files("two weeks")
+            .build();
+
+        assert!(matches!(
+            result,
+            Err(DailyRotateFileError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_max_total_size() {
+        let temp_dir = setup_temp_dir();
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .max_size(20)
+            .max_total_size(50)
+            .build()
+            .expect("Failed to create transport");
+
+        for i in 0..10 {
+            transport.log(LogInfo::new("info", format!("entry {:02} abcdefgh", i)));
+        }
+        transport.flush().expect("Failed to flush");
+
+        // The cap is enforced at rotation time, when the new active file is still empty
+        let active = transport.file_path.lock().unwrap().clone();
+        let rotated_size: u64 = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path() != active)
+            .map(|entry| entry.metadata().unwrap().len())
+            .sum();
+
+        assert!(
+            rotated_size <= 50,
+            "Expected at most 50 bytes of rotated files, found {}",
+            rotated_size
+        );
+        assert!(
+            rotated_size > 0,
+            "Expected the newest rotated files to be kept"
+        );
+
+        let newest = fs::read_to_string(&*transport.file_path.lock().unwrap()).unwrap();
+        assert_eq!(newest, "entry 09 abcdefgh\n");
+    }
+
+    #[test]
+    fn test_max_size_accepts_size_strings() {
+        let temp_dir = setup_temp_dir();
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .max_size("10m")
+            .max_total_size("1g")
+            .build()
+            .expect("Failed to create transport");
+        assert_eq!(transport.options.max_size, Some(10 * 1024 * 1024));
+        assert_eq!(transport.options.max_total_size, Some(1024 * 1024 * 1024));
+
+        let result = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .max_size("10 megs")
+            .build();
+        assert!(matches!(
+            result,
+            Err(DailyRotateFileError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_background_compression() {
+        let temp_dir = setup_temp_dir();
+        let archived = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&archived);
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .max_size(80)
+            .zipped_archive(true)
+            .background_compression(true)
+            .on_archive(move |path| sink.lock().unwrap().push(path.to_path_buf()))
+            .build()
+            .expect("Failed to create transport");
+
+        // 15 bytes per entry, so every fifth entry fills the 80 byte file
+        for i in 0..12 {
+            transport.log(LogInfo::new("info", format!("Test message {}", i % 10)));
+        }
+        transport.flush().expect("Failed to flush");
+
+        let archived = archived.lock().unwrap();
+        assert_eq!(archived.len(), 2, "Expected 2 archives once flushed");
+        assert!(archived.iter().all(|path| path.exists()));
+    }
+
+    #[test]
+    fn test_interrupted_compression_is_redone() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        let temp_dir = setup_temp_dir();
+        let source = temp_dir.path().join("test.log.2020-01-01");
+        let partial = temp_dir.path().join("test.log.2020-01-01.gz");
+        let marker = temp_dir.path().join(".test.log.2020-01-01.gz.pending");
+        fs::write(&source, "entry before the crash\n").unwrap();
+        fs::write(&partial, "truncated gzip").unwrap();
+        fs::write(&marker, source.to_string_lossy().as_bytes()).unwrap();
+
+        let _transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .zipped_archive(true)
+            .build()
+            .expect("Failed to create transport");
+
+        assert!(
+            !source.exists(),
+            "Expected the source to be compressed again"
+        );
+        assert!(!marker.exists(), "Expected the marker to be cleared");
+
+        let mut contents = String::new();
+        GzDecoder::new(File::open(&partial).expect("Expected a rebuilt archive"))
+            .read_to_string(&mut contents)
+            .expect("Expected a valid gzip archive");
+        assert_eq!(contents, "entry before the crash\n");
+    }
+
+    #[test]
+    fn test_compression_interrupted_before_the_archive_is_redone() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        // A crash right after the marker was written leaves no archive at all
+        let temp_dir = setup_temp_dir();
+        let source = temp_dir.path().join("test.log.2020-01-01");
+        let marker = temp_dir.path().join(".test.log.2020-01-01.gz.pending");
+        fs::write(&source, "entry before the crash\n").unwrap();
+        fs::write(&marker, source.to_string_lossy().as_bytes()).unwrap();
+
+        let _transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .zipped_archive(true)
+            .build()
+            .expect("Failed to create transport");
+
+        assert!(!source.exists(), "Expected the source to be compressed");
+        assert!(!marker.exists(), "Expected the marker to be cleared");
+
+        let archive = temp_dir.path().join("test.log.2020-01-01.gz");
+        let mut contents = String::new();
+        GzDecoder::new(File::open(&archive).expect("Expected an archive"))
+            .read_to_string(&mut contents)
+            .expect("Expected a valid gzip archive");
+        assert_eq!(contents, "entry before the crash\n");
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_archives_are_rotated_and_pruned() {
+        let temp_dir = setup_temp_dir();
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .max_size(10)
+            .max_files(2)
+            .compression(Compression::Zstd { level: 3 })
+            .build()
+            .expect("Failed to create transport");
+
+        for i in 0..5 {
+            transport.log(LogInfo::new("info", format!("message number {}", i)));
+        }
+        transport.flush().expect("Failed to flush");
+
+        let extensions: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                entry
+                    .path()
+                    .extension()
+                    .map(|ext| ext.to_string_lossy().into_owned())
+            })
+            .collect();
+
+        assert_eq!(
+            extensions.len(),
+            2,
+            "Expected max_files to count .zst archives"
+        );
+        assert!(extensions.iter().any(|ext| ext == "zst"));
+    }
+
+    #[test]
+    fn test_compression_levels_round_trip() {
+        use flate2::read::GzDecoder;
+        use std::io::Read;
+
+        for level in 0..=9 {
+            let temp_dir = setup_temp_dir();
+            let transport = DailyRotateFile::builder()
+                .filename(temp_dir.path().join("test.log"))
+                .format(message_only())
+                .max_size(1700) // 40 entries of 41 bytes fit, the final 101 byte entry does not
+                .zipped_archive(true)
+                .compression_level(level)
+                .compression_buffer_size(16)
+                .build()
+                .expect("Failed to create transport");
+
+            let mut expected = String::new();
+            for i in 0..40 {
+                let message = format!("level {} entry {:02} with repetitive padding", level, i);
+                expected.push_str(&message);
+                expected.push('\n');
+                transport.log(LogInfo::new("info", message));
+            }
+            // Rotates the 40 entries above into an archive
+            transport.log(LogInfo::new("info", "x".repeat(100)));
+            transport.flush().expect("Failed to flush");
+
+            let archive = fs::read_dir(temp_dir.path())
+                .unwrap()
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .find(|path| Compression::is_archive(path))
+                .expect("Expected an archive");
+
+            let mut contents = String::new();
+            GzDecoder::new(File::open(archive).unwrap())
+                .read_to_string(&mut contents)
+                .expect("Expected a valid gzip archive");
+            assert_eq!(contents, expected, "Round trip failed at level {}", level);
+        }
+    }
+
+    #[test]
+    fn test_invalid_compression_settings_are_rejected() {
+        let temp_dir = setup_temp_dir();
+        let build = |builder: DailyRotateFileBuilder| {
+            builder.filename(temp_dir.path().join("test.log")).build()
+        };
+
+        let too_high = build(
+            DailyRotateFile::builder()
+                .zipped_archive(true)
+                .compression_level(10),
+        );
+        let gzip_too_high =
+            build(DailyRotateFile::builder().compression(Compression::Gzip { level: 10 }));
+        let without_gzip = build(DailyRotateFile::builder().compression_level(5));
+        let empty_buffer = build(DailyRotateFile::builder().compression_buffer_size(0));
+
+        for result in [too_high, gzip_too_high, without_gzip, empty_buffer] {
+            assert!(matches!(
+                result,
+                Err(DailyRotateFileError::InvalidConfig(_))
+            ));
+        }
+
+        #[cfg(feature = "bzip2")]
+        assert!(matches!(
+            build(
+                DailyRotateFile::builder()
+                    .compression(Compression::Bzip2 { level: 0 })
+                    .max_size(10)
+            ),
+            Err(DailyRotateFileError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_compress_after_keeps_recent_files_plain() {
+        let temp_dir = setup_temp_dir();
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .max_size(10)
+            .zipped_archive(true)
+            .compress_after(1)
+            .build()
+            .expect("Failed to create transport");
+
+        for i in 0..5 {
+            transport.log(LogInfo::new("info", format!("message number {}", i)));
+        }
+        transport.flush().expect("Failed to flush");
+
+        let active = transport.file_path.lock().unwrap().clone();
+        let (archives, plain): (Vec<_>, Vec<_>) = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path != &active)
+            .partition(|path| Compression::is_archive(path));
+
+        assert_eq!(
+            plain.len(),
+            1,
+            "Expected the latest rotated file to stay plain"
+        );
+        assert_eq!(
+            archives.len(),
+            4,
+            "Expected older rotated files to be compressed"
+        );
+    }
+
+    #[test]
+    fn test_compress_after_age_defers_compression() {
+        let temp_dir = setup_temp_dir();
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .max_size(10)
+            .zipped_archive(true)
+            .compress_after_age(Duration::from_secs(3600))
+            .build()
+            .expect("Failed to create transport");
+
+        for i in 0..3 {
+            transport.log(LogInfo::new("info", format!("message number {}", i)));
+        }
+        transport.flush().expect("Failed to flush");
+
+        let archives = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| Compression::is_archive(&entry.path()))
+            .count();
+        assert_eq!(
+            archives, 0,
+            "Expected files younger than an hour to stay plain"
+        );
+    }
+
+    #[test]
+    fn test_compress_after_age_compresses_aged_files() {
+        let temp_dir = setup_temp_dir();
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .max_size(10)
+            .zipped_archive(true)
+            .compress_after_age(Duration::from_secs(3600))
+            .clock(Arc::clone(&clock))
+            .build()
+            .expect("Failed to create transport");
+
+        let count_files = || {
+            let active = transport.file_path.lock().unwrap().clone();
+            let (archives, plain): (Vec<_>, Vec<_>) = fs::read_dir(temp_dir.path())
+                .unwrap()
+                .filter_map(|entry| entry.ok())
+                .map(|entry| entry.path())
+                .filter(|path| path != &active)
+                .partition(|path| Compression::is_archive(path));
+            (archives.len(), plain.len())
+        };
+
+        for i in 0..3 {
+            transport.log(LogInfo::new("info", format!("message number {}", i)));
+        }
+        transport.flush().expect("Failed to flush");
+        assert_eq!(count_files(), (0, 3));
+
+        clock.advance(chrono::Duration::hours(2));
+        transport.log(LogInfo::new("info", "message number 3"));
+        transport.flush().expect("Failed to flush");
+        assert_eq!(
+            count_files(),
+            (4, 0),
+            "Expected files older than an hour to be compressed"
+        );
+    }
+
+    #[test]
+    fn test_max_total_size_respects_compress_after() {
+        let temp_dir = setup_temp_dir();
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .max_size(10)
+            .max_total_size("1g")
+            .zipped_archive(true)
+            .compress_after(2)
+            .build()
+            .expect("Failed to create transport");
+
+        for i in 0..5 {
+            transport.log(LogInfo::new("info", format!("message number {}", i)));
+        }
+        transport.flush().expect("Failed to flush");
+
+        let active = transport.file_path.lock().unwrap().clone();
+        let (archives, plain): (Vec<_>, Vec<_>) = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path != &active)
+            .partition(|path| Compression::is_archive(path));
+
+        assert_eq!(
+            plain.len(),
+            2,
+            "Expected the size cap to leave the 2 latest rotated files plain"
+        );
+        assert_eq!(archives.len(), 3);
+    }
+
+    #[test]
+    fn test_max_files_cleanup() {
+        let temp_dir = setup_temp_dir();
+        let clock = Arc::new(ManualClock::new(Utc::now()));
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            //.filename("logs/test.log")
+            .date_pattern("%Y-%m-%d_%H-%M-%S")
+            .max_files(2)
+            .clock(Arc::clone(&clock))
+            .build()
+            .expect("Failed to create transport");
+
+        for i in 0..5 {
+            transport.log(LogInfo {
+                level: "info".to_string(),
+                message: format!("Message {}", i),
                 meta: Default::default(),
             });
 
-            // simulate date change
-            std::thread::sleep(std::time::Duration::from_secs(1));
-        }
+            // simulate date change
+            clock.advance(chrono::Duration::seconds(1));
+        }
+
+        transport.flush().expect("Failed to flush");
+
+        let files: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_file())
+            .collect();
+
+        assert_eq!(files.len(), 2, "Expected exactly 2 log files after cleanup");
+    }
+
+    #[test]
+    fn test_archive_dir_with_date_subfolders() {
+        let temp_dir = setup_temp_dir();
+        let archive_dir = temp_dir.path().join("archive");
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .max_size(10)
+            .zipped_archive(true)
+            .archive_dir(&archive_dir)
+            .archive_subdir("%Y/%m")
+            .build()
+            .expect("Failed to create transport");
 
+        for i in 0..3 {
+            transport.log(LogInfo::new("info", format!("message number {}", i)));
+        }
         transport.flush().expect("Failed to flush");
 
-        let files: Vec<_> = fs::read_dir(temp_dir.path())
+        let month_dir = archive_dir.join(Local::now().format("%Y/%m").to_string());
+        let archives: Vec<_> = fs::read_dir(&month_dir)
+            .expect("Expected a dated archive subfolder")
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .collect();
+        assert_eq!(archives.len(), 3, "Expected 3 archives in {:?}", month_dir);
+        assert!(archives.iter().all(|path| Compression::is_archive(path)));
+
+        let log_files = fs::read_dir(temp_dir.path())
             .unwrap()
             .filter_map(|entry| entry.ok())
             .filter(|entry| entry.path().is_file())
+            .count();
+        assert_eq!(log_files, 1, "Expected only the active file in dirname");
+    }
+
+    #[test]
+    fn test_archive_dir_is_covered_by_max_files() {
+        let temp_dir = setup_temp_dir();
+        let archive_dir = temp_dir.path().join("archive");
+        let clock = Arc::new(ManualClock::new(
+            DateTime::parse_from_rfc3339("2026-10-16T12:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        ));
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .utc(true)
+            .clock(Arc::clone(&clock))
+            .max_size(10)
+            .max_files(2)
+            .archive_dir(&archive_dir)
+            .build()
+            .expect("Failed to create transport");
+
+        for i in 0..6 {
+            transport.log(LogInfo::new("info", format!("message number {}", i)));
+        }
+        transport.flush().expect("Failed to flush");
+
+        let archived: Vec<_> = fs::read_dir(&archive_dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
             .collect();
+        assert_eq!(
+            archived.len(),
+            1,
+            "Expected 1 moved file besides the active one"
+        );
+        assert_eq!(archived[0].file_name().unwrap(), "test.log_5.2026-10-16");
+        assert!(transport.file_path.lock().unwrap().exists());
+    }
 
-        assert_eq!(files.len(), 2, "Expected exactly 2 log files after cleanup");
+    #[test]
+    fn test_archive_subdir_requires_archive_dir() {
+        let temp_dir = setup_temp_dir();
+        let result = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .archive_subdir("%Y/%m")
+            .build();
+
+        assert!(matches!(
+            result,
+            Err(DailyRotateFileError::InvalidConfig(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_follows_active_file() {
+        let temp_dir = setup_temp_dir();
+        let link = temp_dir.path().join("test.log.current");
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .format(message_only())
+            .max_size(10)
+            .max_files(2)
+            .create_symlink(true)
+            .symlink_name("test.log.current")
+            .build()
+            .expect("Failed to create transport");
+
+        for i in 0..4 {
+            transport.log(LogInfo::new("info", format!("message number {}", i)));
+            let active = transport.file_path.lock().unwrap().clone();
+            assert_eq!(
+                fs::canonicalize(&link).expect("Expected the symlink to resolve"),
+                fs::canonicalize(&active).unwrap()
+            );
+        }
+
+        assert!(
+            fs::symlink_metadata(&link)
+                .unwrap()
+                .file_type()
+                .is_symlink(),
+            "Expected cleanup to leave the symlink alone"
+        );
+        assert!(
+            fs::read_link(&link).unwrap().is_relative(),
+            "Expected a link relative to dirname"
+        );
+    }
+
+    #[test]
+    fn test_symlink_name_must_be_a_file_name() {
+        let temp_dir = setup_temp_dir();
+        let result = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .create_symlink(true)
+            .symlink_name("nested/current.log")
+            .build();
+
+        assert!(matches!(
+            result,
+            Err(DailyRotateFileError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_filename_template_with_counter() {
+        let temp_dir = setup_temp_dir();
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("app-%DATE%"))
+            .extension(".log")
+            .format(message_only())
+            .max_size(10)
+            .build()
+            .expect("Failed to create transport");
+
+        for i in 0..3 {
+            transport.log(LogInfo::new("info", format!("message number {}", i)));
+        }
+        transport.flush().expect("Failed to flush");
+
+        let date_str = Local::now().format("%Y-%m-%d").to_string();
+        let active = transport.file_path.lock().unwrap().clone();
+        assert_eq!(
+            active.file_name().unwrap().to_string_lossy(),
+            format!("app-{}.3.log", date_str)
+        );
+
+        let mut files: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                format!("app-{}.1.log", date_str),
+                format!("app-{}.2.log", date_str),
+                format!("app-{}.3.log", date_str),
+                format!("app-{}.log", date_str),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_counter_is_not_reused_after_compression() {
+        let temp_dir = setup_temp_dir();
+        let clock = Arc::new(ManualClock::new(
+            DateTime::parse_from_rfc3339("2026-10-16T12:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        ));
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("app-%DATE%.log"))
+            .format(message_only())
+            .utc(true)
+            .clock(Arc::clone(&clock))
+            .max_size(10)
+            .zipped_archive(true)
+            .build()
+            .expect("Failed to create transport");
+
+        for i in 0..3 {
+            transport.log(LogInfo::new("info", format!("message number {}", i)));
+        }
+        transport.flush().expect("Failed to flush");
+
+        let mut files: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                "app-2026-10-16.1.log.gz",
+                "app-2026-10-16.2.log.gz",
+                "app-2026-10-16.3.log",
+                "app-2026-10-16.log.gz",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filename_variables() {
+        let temp_dir = setup_temp_dir();
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("app-%REGION%-%PID%-%DATE%.log"))
+            .filename_variable("REGION", "eu")
+            .build()
+            .expect("Failed to create transport");
+
+        let date_str = Local::now().format("%Y-%m-%d").to_string();
+        let active = transport.file_path.lock().unwrap().clone();
+        assert_eq!(
+            active.file_name().unwrap().to_string_lossy(),
+            format!("app-eu-{}-{}.log", std::process::id(), date_str)
+        );
+
+        let escaping = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("app-%REGION%.log"))
+            .filename_variable("REGION", "../eu")
+            .build();
+        assert!(matches!(
+            escaping,
+            Err(DailyRotateFileError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_pid_files_of_earlier_runs_are_pruned() {
+        let temp_dir = setup_temp_dir();
+        let clock = Arc::new(ManualClock::new(
+            DateTime::parse_from_rfc3339("2026-10-16T12:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        ));
+
+        // Three restarts, each with its own process id
+        for pid in [101, 102, 103] {
+            let transport = DailyRotateFile::builder()
+                .filename(temp_dir.path().join("app-%PID%-%DATE%.log"))
+                .filename_variable("PID", pid)
+                .date_pattern("%Y-%m-%d_%H-%M")
+                .format(message_only())
+                .utc(true)
+                .clock(Arc::clone(&clock))
+                .max_size(10)
+                .max_files(2)
+                .build()
+                .expect("Failed to create transport");
+
+            transport.log(LogInfo::new("info", format!("entry of process {}", pid)));
+            transport.flush().expect("Failed to flush");
+            clock.advance(chrono::Duration::minutes(1));
+        }
+
+        let mut files: Vec<String> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                "app-103-2026-10-16_12-02.1.log",
+                "app-103-2026-10-16_12-02.log"
+            ],
+            "Expected the files of earlier processes to count towards max_files"
+        );
+    }
+
+    #[test]
+    fn test_dir_pattern_nests_and_prunes_directories() {
+        let temp_dir = setup_temp_dir();
+        let old_dir = temp_dir.path().join("2020/01/02");
+        fs::create_dir_all(&old_dir).unwrap();
+        let old_file = old_dir.join("test.log.2020-01-02");
+        fs::write(&old_file, "old entry\n").unwrap();
+
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .dir_pattern("%Y/%m/%d")
+            .format(message_only())
+            .max_size(10)
+            .max_files("7d")
+            .build()
+            .expect("Failed to create transport");
+
+        let today = Local::now();
+        let active = transport.file_path.lock().unwrap().clone();
+        assert_eq!(
+            active,
+            temp_dir
+                .path()
+                .join(today.format("%Y/%m/%d").to_string())
+                .join(format!("test.log.{}", today.format("%Y-%m-%d")))
+        );
+
+        for i in 0..2 {
+            transport.log(LogInfo::new("info", format!("message number {}", i)));
+        }
+
+        assert!(
+            !old_file.exists(),
+            "Expected the expired file to be removed"
+        );
+        assert!(
+            !temp_dir.path().join("2020").exists(),
+            "Expected the emptied date directories to be removed"
+        );
+        assert!(transport.file_path.lock().unwrap().exists());
+    }
+
+    #[test]
+    fn test_frequency_drives_rotation() {
+        let temp_dir = setup_temp_dir();
+        let clock = Arc::new(ManualClock::new(
+            DateTime::parse_from_rfc3339("2026-10-16T12:14:58Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        ));
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .utc(true)
+            .frequency("15m")
+            .clock(Arc::clone(&clock))
+            .build()
+            .expect("Failed to create transport");
+
+        clock.advance(chrono::Duration::seconds(1));
+        assert!(
+            !transport.should_rotate(0),
+            "Expected no rotation within the period"
+        );
+
+        clock.advance(chrono::Duration::seconds(1));
+        assert!(
+            transport.should_rotate(0),
+            "Expected a rotation at the boundary"
+        );
+
+        let invalid = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .frequency("weekly")
+            .build();
+        assert!(matches!(
+            invalid,
+            Err(DailyRotateFileError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_schedule_drives_rotation() {
+        let temp_dir = setup_temp_dir();
+        let start = chrono::NaiveDate::from_ymd_opt(2026, 10, 15)
+            .unwrap()
+            .and_hms_opt(23, 0, 0)
+            .unwrap()
+            .and_utc();
+        let clock = Arc::new(ManualClock::new(start));
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .utc(true)
+            .schedule("0 2 * * *")
+            .clock(Arc::clone(&clock))
+            .build()
+            .expect("Failed to create transport");
+
+        assert_eq!(
+            transport.next_rotation(),
+            Some(start + chrono::Duration::hours(3))
+        );
+        clock.advance(chrono::Duration::minutes(179));
+        assert!(!transport.should_rotate(0));
+        clock.advance(chrono::Duration::minutes(1));
+        assert!(
+            transport.should_rotate(0),
+            "Expected the 02:00 rotation to be due"
+        );
+
+        transport.log(LogInfo::new("info", "after the 02:00 rotation"));
+        assert!(!transport.should_rotate(0));
+        assert_eq!(
+            transport.next_rotation(),
+            Some(start + chrono::Duration::hours(27))
+        );
+
+        let combined = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .frequency("1h")
+            .schedule("@hourly")
+            .build();
+        assert!(matches!(
+            combined,
+            Err(DailyRotateFileError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_manual_clock_crosses_month_boundary() {
+        let temp_dir = setup_temp_dir();
+        let clock = Arc::new(ManualClock::new(
+            DateTime::parse_from_rfc3339("2026-10-31T23:59:59Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        ));
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .utc(true)
+            .clock(Arc::clone(&clock))
+            .build()
+            .expect("Failed to create transport");
+
+        transport.log(LogInfo::new("info", "before midnight"));
+        clock.advance(chrono::Duration::seconds(1));
+        transport.log(LogInfo::new("info", "after midnight"));
+        transport.flush().expect("Failed to flush");
+
+        assert!(temp_dir.path().join("test.log.2026-10-31").exists());
+        assert_eq!(
+            *transport.file_path.lock().unwrap(),
+            temp_dir.path().join("test.log.2026-11-01")
+        );
+    }
+
+    #[test]
+    fn test_timezone_splits_files_at_its_midnight() {
+        let temp_dir = setup_temp_dir();
+        let clock = Arc::new(ManualClock::new(
+            DateTime::parse_from_rfc3339("2026-10-17T04:59:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        ));
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .timezone("-05:00")
+            .clock(Arc::clone(&clock))
+            .build()
+            .expect("Failed to create transport");
+
+        assert_eq!(
+            *transport.file_path.lock().unwrap(),
+            temp_dir.path().join("test.log.2026-10-16")
+        );
+
+        clock.advance(chrono::Duration::minutes(1));
+        transport.log(LogInfo::new("info", "midnight at UTC-5"));
+        assert_eq!(
+            *transport.file_path.lock().unwrap(),
+            temp_dir.path().join("test.log.2026-10-17")
+        );
+
+        let invalid = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .timezone("Mars/Olympus_Mons")
+            .build();
+        assert!(matches!(
+            invalid,
+            Err(DailyRotateFileError::InvalidConfig(_))
+        ));
+    }
+
+    // The DST tests need IANA timezones: run them with `cargo test --features chrono-tz`
+    #[cfg(feature = "chrono-tz")]
+    fn utc_instant(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[cfg(feature = "chrono-tz")]
+    #[test]
+    fn test_dst_fall_back_merges_repeated_hour() {
+        let temp_dir = setup_temp_dir();
+        // 01:50 EDT; the clocks go back from 02:00 EDT to 01:00 EST ten minutes later
+        let clock = Arc::new(ManualClock::new(utc_instant("2026-11-01T05:50:00Z")));
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .date_pattern("%Y-%m-%d_%H%M")
+            .frequency("15m")
+            .timezone("America/New_York")
+            .clock(Arc::clone(&clock))
+            .build()
+            .expect("Failed to create transport");
+
+        let log_at = |instant: &str| {
+            clock.set(utc_instant(instant));
+            transport.log(LogInfo::new("info", instant));
+            transport.file_path.lock().unwrap().clone()
+        };
+
+        let first = log_at("2026-11-01T05:55:00Z");
+        assert_eq!(first, temp_dir.path().join("test.log.2026-11-01_0150"));
+
+        // 01:05 and 01:55 EST repeat wall-clock time already covered by the current file
+        assert_eq!(log_at("2026-11-01T06:05:00Z"), first);
+        assert_eq!(log_at("2026-11-01T06:55:00Z"), first);
+
+        assert_eq!(
+            log_at("2026-11-01T07:00:00Z"),
+            temp_dir.path().join("test.log.2026-11-01_0200")
+        );
+        assert_eq!(fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+    }
+
+    #[cfg(feature = "chrono-tz")]
+    #[test]
+    fn test_dst_offset_in_pattern_keeps_repeated_hour_apart() {
+        let temp_dir = setup_temp_dir();
+        let clock = Arc::new(ManualClock::new(utc_instant("2026-11-01T05:30:00Z")));
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .date_pattern("%Y-%m-%d_%H%z")
+            .timezone("America/New_York")
+            .clock(Arc::clone(&clock))
+            .build()
+            .expect("Failed to create transport");
+
+        clock.set(utc_instant("2026-11-01T06:30:00Z"));
+        transport.log(LogInfo::new("info", "01:30 EST"));
+
+        assert!(temp_dir.path().join("test.log.2026-11-01_01-0400").exists());
+        assert_eq!(
+            *transport.file_path.lock().unwrap(),
+            temp_dir.path().join("test.log.2026-11-01_01-0500")
+        );
+    }
+
+    #[cfg(feature = "chrono-tz")]
+    #[test]
+    fn test_dst_spring_forward_fires_skipped_boundary() {
+        let temp_dir = setup_temp_dir();
+        // 01:30 EST; 02:00 never happens, the clocks jump straight to 03:00 EDT
+        let clock = Arc::new(ManualClock::new(utc_instant("2026-03-08T06:30:00Z")));
+        let transport = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .schedule("0 2 * * *")
+            .timezone("America/New_York")
+            .clock(Arc::clone(&clock))
+            .build()
+            .expect("Failed to create transport");
+
+        assert_eq!(
+            transport.next_rotation(),
+            Some(utc_instant("2026-03-08T07:00:00Z"))
+        );
+        clock.set(utc_instant("2026-03-08T06:59:00Z"));
+        assert!(!transport.should_rotate(0));
+        clock.set(utc_instant("2026-03-08T07:00:00Z"));
+        assert!(
+            transport.should_rotate(0),
+            "Expected the 02:00 rotation at 03:00 EDT"
+        );
+    }
+
+    #[test]
+    fn test_invalid_date_pattern_is_rejected() {
+        let temp_dir = setup_temp_dir();
+        let build = |pattern: &str| {
+            DailyRotateFile::builder()
+                .filename(temp_dir.path().join("test.log"))
+                .date_pattern(pattern)
+                .build()
+        };
+
+        for pattern in ["%Q", "%Y-%m-%d %H:%M", "%Y%n%m", "%Y/%m/%d"] {
+            assert!(
+                matches!(build(pattern), Err(DailyRotateFileError::InvalidPattern(_))),
+                "Expected '{}' to be rejected",
+                pattern
+            );
+        }
+
+        let offset_as_zone_name = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .date_pattern("%Y-%m-%d_%Z")
+            .timezone("+05:30")
+            .build();
+        assert!(matches!(
+            offset_as_zone_name,
+            Err(DailyRotateFileError::InvalidPattern(_))
+        ));
+
+        let bad_dir_pattern = DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .dir_pattern("%Y/%Q")
+            .build();
+        assert!(matches!(
+            bad_dir_pattern,
+            Err(DailyRotateFileError::InvalidPattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_pattern_finer_than_frequency_is_reported() {
+        let temp_dir = setup_temp_dir();
+        let warnings = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&warnings);
+
+        DailyRotateFile::builder()
+            .filename(temp_dir.path().join("test.log"))
+            .date_pattern("%Y-%m-%d_%H-%M")
+            .frequency("daily")
+            .on_warning(move |warning| sink.lock().unwrap().push(warning.to_string()))
+            .on_error(|e| panic!("Unexpected error: {}", e))
+            .build()
+            .expect("Expected a finer pattern to be allowed");
+
+        let warnings = warnings.lock().unwrap();
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("finer than the daily frequency"));
     }
 }
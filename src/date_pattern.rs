@@ -0,0 +1,124 @@
+use crate::error::DailyRotateFileError;
+use crate::timezone::Timezone;
+use chrono::format::{Fixed, Item, Numeric, StrftimeItems};
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Checks that `pattern` is a valid strftime string whose output in `timezone` is safe in a
+/// file name: no `:` (reserved on Windows and macOS) and no control characters such as `%n`.
+/// `%Z` passes only where the zone has a name; offsets are written as `+05:30`.
+///
+/// Path separators are only accepted with `allow_separators`, for the directory patterns;
+/// nesting log files is what `dir_pattern` is for.
+///
+/// An invalid pattern would otherwise panic when the first date is formatted.
+pub(crate) fn validate(
+    pattern: &str,
+    timezone: &Timezone,
+    allow_separators: bool,
+) -> Result<(), DailyRotateFileError> {
+    if StrftimeItems::new(pattern).any(|item| matches!(item, Item::Error)) {
+        return Err(DailyRotateFileError::InvalidPattern(format!(
+            "'{}' is not a valid strftime pattern",
+            pattern
+        )));
+    }
+
+    let sample = timezone.format(&sample_date(), pattern);
+    let unsafe_char =
+        |c: &char| *c == ':' || c.is_control() || (!allow_separators && matches!(c, '/' | '\\'));
+    if let Some(c) = sample.chars().find(unsafe_char) {
+        return Err(DailyRotateFileError::InvalidPattern(format!(
+            "'{}' produces {:?}, which is unsafe in file names",
+            pattern, c
+        )));
+    }
+
+    Ok(())
+}
+
+/// The finest unit `pattern` distinguishes, in minutes: 0 for seconds or finer, `None` for a
+/// pattern without any time of day.
+pub(crate) fn resolution_minutes(pattern: &str) -> Option<u32> {
+    StrftimeItems::new(pattern)
+        .filter_map(|item| match item {
+            Item::Numeric(numeric, _) => match numeric {
+                Numeric::Second | Numeric::Nanosecond | Numeric::Timestamp => Some(0),
+                Numeric::Minute => Some(1),
+                Numeric::Hour | Numeric::Hour12 => Some(60),
+                _ => None,
+            },
+            Item::Fixed(fixed) => match fixed {
+                Fixed::RFC2822 | Fixed::RFC3339 | Fixed::Nanosecond => Some(0),
+                Fixed::Nanosecond3 | Fixed::Nanosecond6 | Fixed::Nanosecond9 => Some(0),
+                _ => None,
+            },
+            _ => None,
+        })
+        .min()
+}
+
+fn sample_date() -> DateTime<Utc> {
+    NaiveDate::from_ymd_opt(2026, 10, 16)
+        .and_then(|date| date.and_hms_opt(13, 45, 30))
+        .unwrap_or_default()
+        .and_utc()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        for valid in [
+            "%Y-%m-%d",
+            "%Y-%m-%d_%H-%M-%S",
+            "%Y%m%dT%H%M",
+            "%Y-%m-%d_%H%z",
+        ] {
+            assert!(
+                validate(valid, &Timezone::Local, false).is_ok(),
+                "Expected '{}' to pass",
+                valid
+            );
+        }
+
+        for invalid in [
+            "%Q", "%Y-%", "%H:%M", "%T", "%Y%n%m", "day\t%d", "%H%:z", "%Y/%m/%d", "%Y\\%m", "%D",
+        ] {
+            assert!(
+                matches!(
+                    validate(invalid, &Timezone::Local, false),
+                    Err(DailyRotateFileError::InvalidPattern(_))
+                ),
+                "Expected '{}' to fail",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_directory_patterns_may_nest() {
+        assert!(validate("%Y/%m/%d", &Timezone::Local, true).is_ok());
+        assert!(validate("%Y\\%m", &Timezone::Local, true).is_ok());
+        assert!(validate("%Y/%H:%M", &Timezone::Local, true).is_err());
+    }
+
+    #[test]
+    fn test_zone_name_depends_on_timezone() {
+        assert!(validate("%Y-%m-%d_%Z", &Timezone::Utc, false).is_ok());
+        assert!(validate("%Y-%m-%d_%Z", &"+05:30".parse().unwrap(), false).is_err());
+        assert!(validate("%Y-%m-%d_%Z", &Timezone::Local, false).is_err());
+
+        #[cfg(feature = "chrono-tz")]
+        assert!(validate("%Y-%m-%d_%Z", &"Asia/Kolkata".parse().unwrap(), false).is_ok());
+    }
+
+    #[test]
+    fn test_resolution_minutes() {
+        assert_eq!(resolution_minutes("%Y-%m-%d"), None);
+        assert_eq!(resolution_minutes("%Y-%m-%d_%H"), Some(60));
+        assert_eq!(resolution_minutes("%Y-%m-%d_%H-%M"), Some(1));
+        assert_eq!(resolution_minutes("%s"), Some(0));
+    }
+}
@@ -0,0 +1,55 @@
+use std::fmt;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum DailyRotateFileError {
+    /// A log file or directory could not be created, opened or written.
+    Io(std::io::Error),
+    /// The date pattern cannot be used to build file names.
+    InvalidPattern(String),
+    /// The builder options are missing or contradict each other.
+    InvalidConfig(String),
+    /// A rotated file could not be compressed.
+    Compression {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    /// An old log file or the log directory could not be cleaned up.
+    Cleanup {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl fmt::Display for DailyRotateFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DailyRotateFileError::Io(e) => write!(f, "I/O error: {}", e),
+            DailyRotateFileError::InvalidPattern(msg) => write!(f, "Invalid date pattern: {}", msg),
+            DailyRotateFileError::InvalidConfig(msg) => write!(f, "Invalid configuration: {}", msg),
+            DailyRotateFileError::Compression { path, source } => {
+                write!(f, "Failed to compress {}: {}", path.display(), source)
+            }
+            DailyRotateFileError::Cleanup { path, source } => {
+                write!(f, "Failed to clean up {}: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DailyRotateFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DailyRotateFileError::Io(e) => Some(e),
+            DailyRotateFileError::Compression { source, .. } => Some(source),
+            DailyRotateFileError::Cleanup { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for DailyRotateFileError {
+    fn from(e: std::io::Error) -> Self {
+        DailyRotateFileError::Io(e)
+    }
+}
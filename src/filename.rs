@@ -0,0 +1,326 @@
+use crate::compression::Compression;
+use std::collections::HashMap;
+
+/// The placeholder replaced by the formatted date, as in winston-daily-rotate-file.
+pub(crate) const DATE_PLACEHOLDER: &str = "%DATE%";
+
+/// The variable holding the process id, which changes on every restart.
+const PID_VARIABLE: &str = "PID";
+
+/// Stands for the `%PID%` value in the stored name parts: the current process id when
+/// rendering, any run of digits when matching.
+const PID_WILDCARD: char = '\0';
+
+/// How log file names are built from the configured `filename` and `extension`, and read back
+/// during cleanup.
+///
+/// A name is `<prefix><date><infix>[<separator><counter>]<extension>`: `app-%DATE%.log` gives
+/// `app-2026-10-16.log`, then `app-2026-10-16.1.log` once `max_size` is reached. A `filename`
+/// without the placeholder and without an `extension` keeps the original
+/// `app.log.2026-10-16` / `app.log_1.2026-10-16` naming.
+///
+/// `%PID%` matches any process id, so cleanup still covers the files of earlier runs.
+#[derive(Debug, Clone)]
+pub(crate) struct FileNameTemplate {
+    prefix: String,
+    infix: String,
+    extension: String,
+    legacy: bool,
+    pid: String,
+}
+
+impl FileNameTemplate {
+    /// `file_name` is the last component of the configured `filename`, with each `%NAME%` token
+    /// of `variables` replaced; `extension` is appended after the date and counter, with or
+    /// without its leading dot.
+    pub(crate) fn new(
+        file_name: &str,
+        extension: Option<&str>,
+        variables: &HashMap<String, String>,
+    ) -> Self {
+        let file_name = &variables
+            .iter()
+            .fold(file_name.to_string(), |name, (key, value)| {
+                let value = match key.as_str() {
+                    PID_VARIABLE => PID_WILDCARD.to_string(),
+                    _ => value.clone(),
+                };
+                name.replace(&format!("%{}%", key), &value)
+            });
+        let pid = variables.get(PID_VARIABLE).cloned().unwrap_or_default();
+        let extension = match extension.filter(|ext| !ext.is_empty()) {
+            Some(ext) if ext.starts_with('.') => ext.to_string(),
+            Some(ext) => format!(".{}", ext),
+            None => String::new(),
+        };
+
+        match file_name.split_once(DATE_PLACEHOLDER) {
+            Some((prefix, suffix)) => {
+                // Without an explicit extension, the one in the template keeps its place last
+                let (infix, extension) = if extension.is_empty() {
+                    match suffix.rfind('.') {
+                        Some(dot) => (suffix[..dot].to_string(), suffix[dot..].to_string()),
+                        None => (suffix.to_string(), String::new()),
+                    }
+                } else {
+                    (suffix.to_string(), extension)
+                };
+
+                FileNameTemplate {
+                    prefix: prefix.to_string(),
+                    infix,
+                    extension,
+                    legacy: false,
+                    pid,
+                }
+            }
+            None if extension.is_empty() => FileNameTemplate {
+                prefix: file_name.to_string(),
+                infix: String::new(),
+                extension,
+                legacy: true,
+                pid,
+            },
+            None => FileNameTemplate {
+                prefix: format!("{}.", file_name),
+                infix: String::new(),
+                extension,
+                legacy: false,
+                pid,
+            },
+        }
+    }
+
+    /// The file name for the period formatted as `date`, with the size-rotation `counter`
+    /// left out when it is 0.
+    pub(crate) fn render(&self, date: &str, counter: u32) -> String {
+        let name = if self.legacy {
+            let name = format!("{}.{}", self.prefix, date);
+            match (counter, name.rsplit_once('.')) {
+                (0, _) | (_, None) => name,
+                (_, Some((stem, ext))) => format!("{}_{}.{}", stem, counter, ext),
+            }
+        } else if counter == 0 {
+            format!("{}{}{}{}", self.prefix, date, self.infix, self.extension)
+        } else {
+            format!(
+                "{}{}{}.{}{}",
+                self.prefix, date, self.infix, counter, self.extension
+            )
+        };
+
+        name.replace(PID_WILDCARD, &self.pid)
+    }
+
+    /// The size-rotation counter of `file_name`, archived or not, if it is one of this
+    /// template's names for the period formatted as `date`.
+    pub(crate) fn counter(&self, file_name: &str, date: &str) -> Option<u32> {
+        let name = Compression::strip_archive_extension(file_name);
+        if name == self.render(date, 0) {
+            return Some(0);
+        }
+
+        // Whatever surrounds the counter in a rendered name surrounds it in `name` too
+        let placeholder = u32::MAX.to_string();
+        let sample = self.render(date, u32::MAX);
+        let (before, after) = sample.split_once(&placeholder)?;
+        let digits = name.strip_prefix(before)?.strip_suffix(after)?;
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        digits.parse().ok()
+    }
+
+    /// Whether `file_name` was produced by this template, archived or not.
+    pub(crate) fn matches(&self, file_name: &str) -> bool {
+        if self.legacy {
+            // "basename.date" and "basename_N.date" formats
+            return strip_prefix(file_name, &format!("{}.", self.prefix)).is_some()
+                || strip_prefix(file_name, &format!("{}_", self.prefix)).is_some();
+        }
+
+        !self.date_candidates(file_name).is_empty()
+    }
+
+    /// The substrings of `file_name` that may hold the formatted date, most likely first.
+    pub(crate) fn date_candidates<'a>(&self, file_name: &'a str) -> Vec<&'a str> {
+        let file_name = Compression::strip_archive_extension(file_name);
+        let rest = match strip_prefix(file_name, &self.prefix) {
+            Some(rest) => rest,
+            None => return Vec::new(),
+        };
+
+        if self.legacy {
+            // Skip the "_N" counter that may precede the date ("basename_N.date")
+            let rest = rest.trim_start_matches(|c: char| c == '_' || c.is_ascii_digit());
+            let date = rest.strip_prefix('.').unwrap_or(rest);
+
+            // "basename.date_N" as produced when an archive name was already taken
+            return std::iter::once(date)
+                .chain(date.rsplit_once('_').map(|(date, _)| date))
+                .collect();
+        }
+
+        let Some(rest) = strip_suffix(rest, &self.extension) else {
+            return Vec::new();
+        };
+        [Some(rest), strip_counter(rest, '.')]
+            .into_iter()
+            .flatten()
+            .filter_map(|rest| strip_suffix(rest, &self.infix))
+            .filter(|date| !date.is_empty())
+            .collect()
+    }
+}
+
+/// The name of this machine for the `%HOST%` token, or `localhost` when it cannot be determined.
+pub(crate) fn hostname() -> String {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .chain(
+            ["/proc/sys/kernel/hostname", "/etc/hostname"]
+                .iter()
+                .filter_map(|path| std::fs::read_to_string(path).ok()),
+        )
+        .map(|name| name.trim().to_string())
+        .find(|name| !name.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+/// `name` without a leading `pattern`, where each `PID_WILDCARD` in `pattern` stands for one
+/// or more digits.
+fn strip_prefix<'a>(name: &'a str, pattern: &str) -> Option<&'a str> {
+    let mut rest = name;
+    for (index, literal) in pattern.split(PID_WILDCARD).enumerate() {
+        if index > 0 {
+            let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            if digits == 0 {
+                return None;
+            }
+            rest = &rest[digits..];
+        }
+        rest = rest.strip_prefix(literal)?;
+    }
+    Some(rest)
+}
+
+/// `name` without a trailing `pattern`, where each `PID_WILDCARD` in `pattern` stands for one
+/// or more digits.
+fn strip_suffix<'a>(name: &'a str, pattern: &str) -> Option<&'a str> {
+    let mut rest = name;
+    for (index, literal) in pattern.rsplit(PID_WILDCARD).enumerate() {
+        if index > 0 {
+            let digits = rest.len() - rest.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+            if digits == 0 {
+                return None;
+            }
+            rest = &rest[..rest.len() - digits];
+        }
+        rest = rest.strip_suffix(literal)?;
+    }
+    Some(rest)
+}
+
+/// `name` without a trailing `<separator><digits>` counter, if it has one.
+fn strip_counter(name: &str, separator: char) -> Option<&str> {
+    let (rest, counter) = name.rsplit_once(separator)?;
+    (!counter.is_empty() && counter.chars().all(|c| c.is_ascii_digit())).then_some(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_names() {
+        let template = FileNameTemplate::new("test.log", None, &HashMap::new());
+        assert_eq!(template.render("2026-10-16", 0), "test.log.2026-10-16");
+        assert_eq!(template.render("2026-10-16", 1), "test.log_1.2026-10-16");
+        assert!(template.matches("test.log_1.2026-10-16.gz"));
+        assert_eq!(
+            template.date_candidates("test.log_1.2026-10-16.gz")[0],
+            "2026-10-16"
+        );
+    }
+
+    #[test]
+    fn test_date_placeholder_keeps_extension_last() {
+        let template = FileNameTemplate::new("app-%DATE%.log", None, &HashMap::new());
+        assert_eq!(template.render("2026-10-16", 0), "app-2026-10-16.log");
+        assert_eq!(template.render("2026-10-16", 1), "app-2026-10-16.1.log");
+
+        for name in [
+            "app-2026-10-16.log",
+            "app-2026-10-16.1.log",
+            "app-2026-10-16.12.log.gz",
+        ] {
+            assert!(template.matches(name), "Expected '{}' to match", name);
+            assert!(template.date_candidates(name).contains(&"2026-10-16"));
+        }
+        assert!(!template.matches("app-2026-10-16.log_2.gz"));
+        assert!(!template.matches("other-2026-10-16.log"));
+        assert!(!template.matches("app-2026-10-16.txt"));
+    }
+
+    #[test]
+    fn test_counter() {
+        let template = FileNameTemplate::new("app-%DATE%.log", None, &HashMap::new());
+        assert_eq!(
+            template.counter("app-2026-10-16.log", "2026-10-16"),
+            Some(0)
+        );
+        assert_eq!(
+            template.counter("app-2026-10-16.3.log.gz", "2026-10-16"),
+            Some(3)
+        );
+        assert_eq!(template.counter("app-2026-10-17.3.log", "2026-10-16"), None);
+
+        let legacy = FileNameTemplate::new("test.log", None, &HashMap::new());
+        assert_eq!(
+            legacy.counter("test.log_2.2026-10-16.gz", "2026-10-16"),
+            Some(2)
+        );
+        assert_eq!(legacy.counter("test.log.2026-10-16", "2026-10-16"), Some(0));
+    }
+
+    #[test]
+    fn test_extension_option() {
+        let templated = FileNameTemplate::new("app-%DATE%", Some("log"), &HashMap::new());
+        assert_eq!(templated.render("2026-10-16", 2), "app-2026-10-16.2.log");
+
+        let plain = FileNameTemplate::new("app", Some(".log"), &HashMap::new());
+        assert_eq!(plain.render("2026-10-16", 0), "app.2026-10-16.log");
+        assert_eq!(plain.render("2026-10-16", 1), "app.2026-10-16.1.log");
+        assert!(plain
+            .date_candidates("app.2026-10-16.1.log")
+            .contains(&"2026-10-16"));
+    }
+
+    #[test]
+    fn test_variables_are_substituted() {
+        let variables = HashMap::from([
+            ("HOST".to_string(), "web-1".to_string()),
+            ("PID".to_string(), "4242".to_string()),
+            ("REGION".to_string(), "eu".to_string()),
+        ]);
+        let template =
+            FileNameTemplate::new("app-%REGION%-%HOST%-%PID%-%DATE%.log", None, &variables);
+
+        assert_eq!(
+            template.render("2026-10-16", 1),
+            "app-eu-web-1-4242-2026-10-16.1.log"
+        );
+        assert!(template.matches("app-eu-web-1-4242-2026-10-16.log"));
+        assert!(!template.matches("app-eu-web-2-4242-2026-10-16.log"));
+
+        // Files of earlier processes on this host still belong to the template
+        assert!(template.matches("app-eu-web-1-17-2026-10-16.3.log.gz"));
+        assert_eq!(
+            template.date_candidates("app-eu-web-1-17-2026-10-16.log")[0],
+            "2026-10-16"
+        );
+        assert!(!template.matches("app-eu-web-1--2026-10-16.log"));
+        assert!(!template.matches("app-eu-web-1-x1-2026-10-16.log"));
+    }
+}
@@ -0,0 +1,141 @@
+use crate::error::DailyRotateFileError;
+use chrono::{NaiveDateTime, NaiveTime, Timelike};
+use std::fmt;
+use std::str::FromStr;
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// How often the log file is rotated, as accepted by `frequency` in winston-daily-rotate-file.
+///
+/// Periods are aligned to wall-clock boundaries counted from midnight: `15m` rotates at :00,
+/// :15, :30 and :45, `6h` at 00:00, 06:00, 12:00 and 18:00.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    /// Every this many minutes, from 1 to 1440.
+    Minutes(u32),
+    /// Every this many hours, from 1 to 24.
+    Hours(u32),
+    /// At midnight.
+    Daily,
+}
+
+impl Frequency {
+    /// The start of the period containing `time`, a wall-clock time in the configured timezone.
+    pub fn period_start(&self, time: NaiveDateTime) -> NaiveDateTime {
+        let minute_of_day = time.hour() * 60 + time.minute();
+        let start_minute = match *self {
+            Frequency::Minutes(minutes) => minute_of_day / minutes * minutes,
+            Frequency::Hours(hours) => time.hour() / hours * hours * 60,
+            Frequency::Daily => 0,
+        };
+
+        let start = NaiveTime::from_hms_opt(start_minute / 60, start_minute % 60, 0)
+            .unwrap_or(NaiveTime::MIN);
+        time.date().and_time(start)
+    }
+
+    /// The length of one period in minutes.
+    pub fn minutes(&self) -> u32 {
+        match *self {
+            Frequency::Minutes(minutes) => minutes,
+            Frequency::Hours(hours) => hours * 60,
+            Frequency::Daily => MINUTES_PER_DAY,
+        }
+    }
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Frequency::Minutes(minutes) => write!(f, "{}m", minutes),
+            Frequency::Hours(hours) => write!(f, "{}h", hours),
+            Frequency::Daily => write!(f, "daily"),
+        }
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = DailyRotateFileError;
+
+    /// Parses `"15m"`, `"1h"` and `"daily"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || {
+            DailyRotateFileError::InvalidConfig(format!(
+                "Invalid frequency '{}': expected minutes like \"15m\", hours like \"1h\" or \"daily\"",
+                s
+            ))
+        };
+
+        if s.eq_ignore_ascii_case("daily") {
+            return Ok(Frequency::Daily);
+        }
+
+        let (count, max, frequency): (&str, u32, fn(u32) -> Frequency) =
+            if let Some(minutes) = s.strip_suffix(['m', 'M']) {
+                (minutes, MINUTES_PER_DAY, Frequency::Minutes)
+            } else if let Some(hours) = s.strip_suffix(['h', 'H']) {
+                (hours, 24, Frequency::Hours)
+            } else {
+                return Err(invalid());
+            };
+
+        match count.trim().parse::<u32>() {
+            Ok(count) if (1..=max).contains(&count) => Ok(frequency(count)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(hour: u32, minute: u32, second: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 16)
+            .unwrap()
+            .and_hms_opt(hour, minute, second)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_frequency() {
+        assert_eq!("15m".parse::<Frequency>().unwrap(), Frequency::Minutes(15));
+        assert_eq!(" 1H ".parse::<Frequency>().unwrap(), Frequency::Hours(1));
+        assert_eq!("Daily".parse::<Frequency>().unwrap(), Frequency::Daily);
+
+        for invalid in ["", "m", "0m", "1441m", "25h", "2d", "weekly"] {
+            assert!(
+                invalid.parse::<Frequency>().is_err(),
+                "Expected '{}' to fail",
+                invalid
+            );
+        }
+        assert_eq!(
+            Frequency::Hours(6)
+                .to_string()
+                .parse::<Frequency>()
+                .unwrap(),
+            Frequency::Hours(6)
+        );
+    }
+
+    #[test]
+    fn test_period_start_is_aligned_to_wall_clock() {
+        assert_eq!(
+            Frequency::Minutes(15).period_start(at(10, 44, 59)),
+            at(10, 30, 0)
+        );
+        assert_eq!(
+            Frequency::Minutes(15).period_start(at(10, 45, 0)),
+            at(10, 45, 0)
+        );
+        assert_eq!(
+            Frequency::Hours(1).period_start(at(23, 59, 59)),
+            at(23, 0, 0)
+        );
+        assert_eq!(Frequency::Hours(6).period_start(at(17, 5, 0)), at(12, 0, 0));
+        assert_eq!(Frequency::Daily.period_start(at(17, 5, 0)), at(0, 0, 0));
+    }
+}
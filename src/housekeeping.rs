@@ -0,0 +1,845 @@
+use crate::audit::AuditLog;
+use crate::compression::Compression;
+use crate::daily_rotate_file::DailyRotateFileOptions;
+use crate::error::DailyRotateFileError;
+use crate::filename::FileNameTemplate;
+use crate::retention::Retention;
+use chrono::format::{Parsed, StrftimeItems};
+use chrono::{DateTime, NaiveTime, Utc};
+use std::collections::HashSet;
+use std::fs::{read_dir, File, OpenOptions};
+use std::io::{BufReader, ErrorKind};
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::JoinHandle;
+
+const PENDING_SUFFIX: &str = ".pending";
+
+/// Compression and retention work that follows a rotation, shared with the background worker.
+pub(crate) struct Housekeeping {
+    pub(crate) options: Arc<DailyRotateFileOptions>,
+    pub(crate) file_path: Arc<Mutex<PathBuf>>, // the active file, which is never archived or deleted
+    pub(crate) audit: Option<Mutex<AuditLog>>,
+}
+
+impl Housekeeping {
+    /// Archives the file that was just rotated out and applies the retention options.
+    pub(crate) fn after_rotation(&self, previous_file_path: &Path) {
+        if self.options.compression.is_some() {
+            if self.options.compress_after > 0 || self.options.compress_after_age.is_some() {
+                if let Err(e) = self.compress_delayed_files() {
+                    self.report_error(DailyRotateFileError::Compression {
+                        path: self.log_dir().to_path_buf(),
+                        source: e,
+                    });
+                }
+            } else {
+                self.archive(previous_file_path);
+            }
+        } else if self.options.archive_dir.is_some() {
+            self.archive(previous_file_path);
+        }
+
+        if let Some(retention) = self.options.max_files {
+            if let Err(e) = self.cleanup_old_files(retention) {
+                self.report_error(DailyRotateFileError::Cleanup {
+                    path: self.log_dir().to_path_buf(),
+                    source: e,
+                });
+            }
+        }
+
+        if let Some(max_total_size) = self.options.max_total_size {
+            if let Err(e) = self.enforce_total_size(max_total_size) {
+                self.report_error(DailyRotateFileError::Cleanup {
+                    path: self.log_dir().to_path_buf(),
+                    source: e,
+                });
+            }
+        }
+    }
+
+    /// Compresses the rotated files that are neither among the `compress_after` most recent ones
+    /// nor younger than `compress_after_age`, like logrotate's `delaycompress`.
+    fn compress_delayed_files(&self) -> std::io::Result<()> {
+        for file in self.files_due_for_compression()? {
+            self.archive(&file);
+        }
+
+        Ok(())
+    }
+
+    /// The plain rotated files outside the window protected by `compress_after` and
+    /// `compress_after_age`.
+    fn files_due_for_compression(&self) -> std::io::Result<Vec<PathBuf>> {
+        let current_path = self.file_path.lock().map(|p| p.clone()).unwrap_or_default();
+        let now = self.options.clock.now();
+
+        let mut plain_files: Vec<PathBuf> = self
+            .tracked_log_files()?
+            .into_iter()
+            .filter(|path| path != &current_path && !Compression::is_archive(path))
+            .collect();
+        // Newest first
+        plain_files.reverse();
+
+        Ok(plain_files
+            .into_iter()
+            .enumerate()
+            .filter(|(index, file)| {
+                let recent_by_count = *index < self.options.compress_after as usize;
+                // Files modified after `now`, as seen by a test clock, count as recent
+                let recent_by_age = self.options.compress_after_age.is_some_and(|max_age| {
+                    let max_age =
+                        chrono::Duration::from_std(max_age).unwrap_or(chrono::Duration::MAX);
+                    file.metadata()
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .is_some_and(|modified| now - DateTime::<Utc>::from(modified) < max_age)
+                });
+                !recent_by_count && !recent_by_age
+            })
+            .map(|(_, file)| file)
+            .collect())
+    }
+
+    /// Compresses `file_path`, or just moves it into `archive_dir` when compression is off,
+    /// reporting the result to `on_archive` or the failure to `on_error`.
+    pub(crate) fn archive(&self, file_path: &Path) -> Option<PathBuf> {
+        let compression = self.options.compression;
+        let result = self
+            .archive_destination(file_path)
+            .and_then(|dest_dir| match compression {
+                Some(compression) => Self::compress_file(
+                    file_path,
+                    &dest_dir,
+                    compression,
+                    self.options.compression_buffer_size,
+                ),
+                None => Self::move_file(file_path, &dest_dir),
+            });
+
+        match result {
+            Ok(archive_path) => {
+                self.update_audit(|audit| audit.rename(file_path, &archive_path));
+                self.remove_empty_parents(file_path);
+
+                if let Some(on_archive) = &self.options.on_archive {
+                    on_archive(&archive_path);
+                }
+                Some(archive_path)
+            }
+            Err(e) => {
+                let path = file_path.to_path_buf();
+                self.report_error(if compression.is_some() {
+                    DailyRotateFileError::Compression { path, source: e }
+                } else {
+                    DailyRotateFileError::Cleanup { path, source: e }
+                });
+                None
+            }
+        }
+    }
+
+    /// The directory `file_path` is archived into: next to itself, or `archive_dir` with the
+    /// optional `archive_subdir` pattern expanded for the file's period, created as needed.
+    fn archive_destination(&self, file_path: &Path) -> std::io::Result<PathBuf> {
+        let archive_dir = match &self.options.archive_dir {
+            Some(archive_dir) => archive_dir,
+            None => return Ok(file_path.parent().unwrap_or(Path::new(".")).to_path_buf()),
+        };
+
+        let dest_dir = match (&self.options.archive_subdir, self.file_date(file_path)) {
+            (Some(pattern), Some(date)) => {
+                let subdir = self.options.timezone.format(&date, pattern);
+                archive_dir.join(subdir)
+            }
+            _ => archive_dir.clone(),
+        };
+
+        std::fs::create_dir_all(&dest_dir)?;
+        Ok(dest_dir)
+    }
+
+    /// The `counter`-th candidate name for `file_path` in `dest_dir`, with `archive_ext`
+    /// appended when it is compressed.
+    fn archive_candidate(
+        file_path: &Path,
+        dest_dir: &Path,
+        archive_ext: Option<&str>,
+        counter: u32,
+    ) -> PathBuf {
+        let base_name = file_path
+            .file_stem()
+            .unwrap_or_else(|| std::ffi::OsStr::new("compressed"))
+            .to_string_lossy();
+
+        let original_ext = file_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("");
+
+        let mut name = if original_ext.is_empty() {
+            base_name.to_string()
+        } else {
+            format!("{}.{}", base_name, original_ext)
+        };
+        if counter > 0 {
+            name = format!("{}_{}", name, counter);
+        }
+        if let Some(archive_ext) = archive_ext {
+            name = format!("{}.{}", name, archive_ext);
+        }
+
+        dest_dir.join(name)
+    }
+
+    /// Compresses `file_path` into `dest_dir` and removes the original, returning the archive path.
+    fn compress_file(
+        file_path: &Path,
+        dest_dir: &Path,
+        compression: Compression,
+        buffer_size: usize,
+    ) -> std::io::Result<PathBuf> {
+        let mut counter = 0;
+
+        loop {
+            let attempt_path = Self::archive_candidate(
+                file_path,
+                dest_dir,
+                Some(compression.extension()),
+                counter,
+            );
+
+            // Recovery deletes the archive a marker points at, so never mark an existing one
+            if attempt_path.exists() {
+                counter += 1;
+                continue;
+            }
+
+            // The marker goes first and outlives a crash at any later point, so the next start
+            // can remove the partial archive and redo it
+            let marker = Self::pending_marker(&attempt_path);
+            std::fs::write(&marker, file_path.to_string_lossy().as_bytes())?;
+
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&attempt_path)
+            {
+                Ok(archive_file) => {
+                    let result =
+                        Self::write_archive(file_path, archive_file, compression, buffer_size)
+                            .and_then(|()| std::fs::remove_file(file_path));
+
+                    if let Err(e) = result {
+                        let _ = std::fs::remove_file(&attempt_path);
+                        let _ = std::fs::remove_file(&marker);
+                        return Err(e);
+                    }
+
+                    std::fs::remove_file(&marker)?;
+                    return Ok(attempt_path);
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let _ = std::fs::remove_file(&marker);
+                    counter += 1;
+                    continue;
+                }
+                Err(e) => {
+                    let _ = std::fs::remove_file(&marker);
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// Moves `file_path` into `dest_dir` under the first free name, returning the new path.
+    fn move_file(file_path: &Path, dest_dir: &Path) -> std::io::Result<PathBuf> {
+        let mut counter = 0;
+        let mut target = Self::archive_candidate(file_path, dest_dir, None, counter);
+        while target.exists() {
+            if target == file_path {
+                return Ok(target);
+            }
+            counter += 1;
+            target = Self::archive_candidate(file_path, dest_dir, None, counter);
+        }
+
+        if std::fs::rename(file_path, &target).is_err() {
+            // The archive directory may live on another file system
+            std::fs::copy(file_path, &target)?;
+            std::fs::remove_file(file_path)?;
+        }
+        Ok(target)
+    }
+
+    fn write_archive(
+        file_path: &Path,
+        archive_file: File,
+        compression: Compression,
+        buffer_size: usize,
+    ) -> std::io::Result<()> {
+        let mut input = BufReader::with_capacity(buffer_size, File::open(file_path)?);
+
+        compression.compress(&mut input, archive_file)?.sync_all()
+    }
+
+    /// The marker that exists while `archive_path` is being written: `.<archive name>.pending`.
+    /// The leading dot keeps it out of the log file listing.
+    fn pending_marker(archive_path: &Path) -> PathBuf {
+        let archive_name = archive_path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .unwrap_or_default();
+        archive_path.with_file_name(format!(".{}{}", archive_name, PENDING_SUFFIX))
+    }
+
+    /// Removes archives left half-written by a crash and returns the files that still need
+    /// compressing.
+    pub(crate) fn recover_interrupted_archives(&self) -> Vec<PathBuf> {
+        let mut sources = Vec::new();
+
+        for marker in self.housekeeping_dir_files() {
+            let archive_name = match marker
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.strip_prefix('.'))
+                .and_then(|name| name.strip_suffix(PENDING_SUFFIX))
+            {
+                Some(archive_name) => archive_name.to_string(),
+                None => continue,
+            };
+
+            let source = match std::fs::read_to_string(&marker) {
+                Ok(source) => PathBuf::from(source),
+                Err(e) => {
+                    self.report_error(DailyRotateFileError::Compression {
+                        path: marker,
+                        source: e,
+                    });
+                    continue;
+                }
+            };
+
+            // Without the source the archive was completed before the crash; only the marker is stale
+            if source.exists() {
+                let partial_archive = marker.with_file_name(archive_name);
+                if let Err(e) = std::fs::remove_file(&partial_archive) {
+                    if e.kind() != ErrorKind::NotFound {
+                        self.report_error(DailyRotateFileError::Compression {
+                            path: partial_archive,
+                            source: e,
+                        });
+                        continue;
+                    }
+                }
+                sources.push(source);
+            }
+
+            let _ = std::fs::remove_file(&marker);
+        }
+
+        sources
+    }
+
+    /// Applies `change` to the audit file, if one is configured, and persists it.
+    pub(crate) fn update_audit<F: FnOnce(&mut AuditLog)>(&self, change: F) {
+        if let Some(audit) = &self.audit {
+            if let Ok(mut audit) = audit.lock() {
+                change(&mut audit);
+                if let Err(e) = audit.save() {
+                    self.report_error(e.into());
+                }
+            }
+        }
+    }
+
+    /// Prunes the files recorded in the audit file according to `retention`.
+    fn cleanup_audited_files(
+        &self,
+        audit: &Mutex<AuditLog>,
+        retention: Retention,
+    ) -> std::io::Result<()> {
+        let current_path = self.file_path.lock().map(|p| p.clone()).unwrap_or_default();
+        let mut audit = audit
+            .lock()
+            .map_err(|_| std::io::Error::other("audit lock poisoned"))?;
+
+        // Forget files that were removed behind our back
+        audit.files.retain(|entry| Path::new(&entry.name).exists());
+
+        // Newest first; entries are appended in creation order, which breaks ties
+        let mut entries = audit.files.clone();
+        entries.reverse();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.date));
+
+        let expired: Vec<PathBuf> = match retention {
+            Retention::Count(max_files) => entries
+                .iter()
+                .skip(max_files as usize)
+                .map(|entry| PathBuf::from(&entry.name))
+                .collect(),
+            Retention::Days(days) => {
+                let cutoff = self.retention_cutoff(days).timestamp_millis();
+                entries
+                    .iter()
+                    .filter(|entry| entry.date < cutoff)
+                    .map(|entry| PathBuf::from(&entry.name))
+                    .collect()
+            }
+        };
+
+        for old_file in expired {
+            // don't delete active log file
+            if old_file == current_path {
+                continue;
+            }
+
+            if self.remove_log_file(&old_file) {
+                audit.remove(&old_file);
+            }
+        }
+
+        audit.save()
+    }
+
+    fn cleanup_old_files(&self, retention: Retention) -> std::io::Result<()> {
+        if let Some(audit) = &self.audit {
+            return self.cleanup_audited_files(audit, retention);
+        }
+
+        let log_files = self.find_log_files()?;
+        let current_path = self.file_path.lock().map(|p| p.clone()).unwrap_or_default();
+
+        match retention {
+            Retention::Count(max_files) => self.keep_newest_files(log_files, max_files),
+            Retention::Days(days) => {
+                let cutoff = self.retention_cutoff(days);
+                for old_file in &log_files {
+                    // don't delete active log file
+                    if old_file == &current_path {
+                        continue;
+                    }
+
+                    if self.file_date(old_file).is_some_and(|date| date < cutoff) {
+                        self.remove_log_file(old_file);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the oldest log files until all of them together take at most `max_total_size`
+    /// bytes. With compression enabled, plain files outside the `compress_after` window are
+    /// compressed before anything is deleted; files inside it are deleted uncompressed if needed.
+    fn enforce_total_size(&self, max_total_size: u64) -> std::io::Result<()> {
+        let current_path = self.file_path.lock().map(|p| p.clone()).unwrap_or_default();
+        let mut log_files = self.tracked_log_files()?;
+
+        if self.options.compression.is_some() {
+            let due = self.files_due_for_compression()?;
+            for file in log_files.iter_mut() {
+                if due.contains(file) {
+                    if let Some(archive_path) = self.archive(file) {
+                        *file = archive_path;
+                    }
+                }
+            }
+        }
+
+        let file_size = |path: &Path| path.metadata().map(|m| m.len()).unwrap_or(0);
+        let mut total_size: u64 = log_files.iter().map(|path| file_size(path)).sum();
+
+        for old_file in &log_files {
+            if total_size <= max_total_size {
+                break;
+            }
+
+            // don't delete active log file
+            if old_file == &current_path {
+                continue;
+            }
+
+            let size = file_size(old_file);
+            if self.remove_log_file(old_file) {
+                self.update_audit(|audit| audit.remove(old_file));
+                total_size = total_size.saturating_sub(size);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// This transport's log files, oldest first: from the audit file when one is configured,
+    /// otherwise from the log directory.
+    fn tracked_log_files(&self) -> std::io::Result<Vec<PathBuf>> {
+        if let Some(audit) = &self.audit {
+            let audit = audit
+                .lock()
+                .map_err(|_| std::io::Error::other("audit lock poisoned"))?;
+            let mut entries = audit.files.clone();
+            entries.sort_by_key(|entry| entry.date);
+            return Ok(entries
+                .into_iter()
+                .map(|entry| PathBuf::from(entry.name))
+                .filter(|path| path.exists())
+                .collect());
+        }
+
+        let mut log_files = self.find_log_files()?;
+        self.sort_oldest_first(&mut log_files);
+        Ok(log_files)
+    }
+
+    /// Orders log files by the period in their name, then by modification time, then by their
+    /// size-rotation counter, so files written within the same clock tick keep a stable order.
+    fn sort_oldest_first(&self, log_files: &mut [PathBuf]) {
+        log_files.sort_by_cached_key(|path| {
+            let modified = path
+                .metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+            (self.file_date(path), modified, self.file_counter(path))
+        });
+    }
+
+    /// Lists this transport's log files in the log and archive directories, zipped ones inclusive.
+    fn find_log_files(&self) -> std::io::Result<Vec<PathBuf>> {
+        let log_dir = self.log_dir();
+
+        let template = self.file_name_template();
+
+        let mut log_files: Vec<PathBuf> = Vec::new();
+
+        if self.options.dir_pattern.is_some() {
+            // dated subdirectories of the log directory
+            let mut nested = Vec::new();
+            Self::collect_files(log_dir, &mut nested)?;
+            log_files.extend(nested.into_iter().filter(|path| {
+                let filename = path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+                template.matches(filename)
+            }));
+        } else {
+            // add all log files, zipped ones inclusive
+            for entry in read_dir(log_dir)? {
+                let entry = entry?;
+                let path = entry.path();
+
+                // file_type does not follow links, so the `create_symlink` link is never a log file
+                if entry.file_type()?.is_file() {
+                    let filename = path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+
+                    // Check if it's one of our log files
+                    if template.matches(filename) {
+                        log_files.push(path);
+                    }
+                }
+            }
+        }
+
+        // archived files, possibly in date subfolders of the archive directory
+        if let Some(archive_dir) = self.separate_archive_dir() {
+            let mut archived = Vec::new();
+            Self::collect_files(archive_dir, &mut archived)?;
+            log_files.extend(archived.into_iter().filter(|path| {
+                let filename = path.file_name().and_then(|s| s.to_str()).unwrap_or("");
+                template.matches(filename)
+            }));
+        }
+
+        // the archive directory may itself lie below a nested log directory
+        let mut seen = HashSet::new();
+        log_files.retain(|path| seen.insert(path.clone()));
+
+        Ok(log_files)
+    }
+
+    /// The configured `archive_dir`, unless it is the log directory itself.
+    fn separate_archive_dir(&self) -> Option<&Path> {
+        self.options
+            .archive_dir
+            .as_deref()
+            .filter(|archive_dir| *archive_dir != self.log_dir())
+    }
+
+    /// Every file in the log directory, recursively with `dir_pattern`, and in the archive
+    /// directory.
+    fn housekeeping_dir_files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        if self.options.dir_pattern.is_some() {
+            let _ = Self::collect_files(self.log_dir(), &mut files);
+        } else if let Ok(entries) = read_dir(self.log_dir()) {
+            files.extend(
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path()),
+            );
+        }
+        if let Some(archive_dir) = self.separate_archive_dir() {
+            let _ = Self::collect_files(archive_dir, &mut files);
+        }
+        let mut seen = HashSet::new();
+        files.retain(|path| seen.insert(path.clone()));
+        files
+    }
+
+    /// Appends the files below `dir` to `files`. A missing directory has no files.
+    fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> std::io::Result<()> {
+        let entries = match read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e),
+        };
+
+        for entry in entries {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                Self::collect_files(&entry.path(), files)?;
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+        Ok(())
+    }
+
+    fn keep_newest_files(&self, mut log_files: Vec<PathBuf>, max_files: u32) {
+        if log_files.len() <= max_files as usize {
+            return;
+        }
+
+        // Newest first
+        self.sort_oldest_first(&mut log_files);
+        log_files.reverse();
+
+        // Keep only max_files
+        for old_file in log_files.iter().skip(max_files as usize) {
+            // don't delete active log file
+            let current_path = self.file_path.lock().map(|p| p.clone()).unwrap_or_default();
+            if old_file == &current_path {
+                continue;
+            }
+
+            if self.options.compression.is_some() && !Compression::is_archive(old_file) {
+                // archive also deletes the original file
+                self.archive(old_file);
+            } else {
+                self.remove_log_file(old_file);
+            }
+        }
+    }
+
+    /// Deletes `path`, reporting it to `on_log_removed` or the failure to `on_error`.
+    fn remove_log_file(&self, path: &Path) -> bool {
+        match std::fs::remove_file(path) {
+            Ok(()) => {
+                self.remove_empty_parents(path);
+                if let Some(on_log_removed) = &self.options.on_log_removed {
+                    on_log_removed(path);
+                }
+                true
+            }
+            Err(e) => {
+                self.report_error(DailyRotateFileError::Cleanup {
+                    path: path.to_path_buf(),
+                    source: e,
+                });
+                false
+            }
+        }
+    }
+
+    /// Removes the dated directories left empty once `path` is gone, up to but excluding the
+    /// log and archive directories themselves.
+    fn remove_empty_parents(&self, path: &Path) {
+        let roots: Vec<&Path> = std::iter::once(self.log_dir())
+            .chain(self.options.archive_dir.as_deref())
+            .collect();
+
+        let mut dir = path.parent();
+        while let Some(current) = dir {
+            let below_root = roots
+                .iter()
+                .any(|root| current != *root && current.starts_with(root));
+            // remove_dir refuses non-empty directories, which ends the walk
+            if !below_root || std::fs::remove_dir(current).is_err() {
+                break;
+            }
+            dir = current.parent();
+        }
+    }
+
+    /// Files whose period started before this instant are expired under `Retention::Days`.
+    fn retention_cutoff(&self, days: u32) -> DateTime<Utc> {
+        self.options.clock.now() - chrono::Duration::days(days as i64)
+    }
+
+    /// The start of the period a log file belongs to, read back from the date in its name.
+    /// Falls back to the file's modification time when the name cannot be parsed.
+    fn file_date(&self, path: &Path) -> Option<DateTime<Utc>> {
+        let filename = path.file_name()?.to_str()?;
+
+        self.file_name_template()
+            .date_candidates(filename)
+            .into_iter()
+            .find_map(|date_str| self.parse_period_date(date_str))
+            .or_else(|| {
+                let modified = path.metadata().ok()?.modified().ok()?;
+                Some(DateTime::<Utc>::from(modified))
+            })
+    }
+
+    /// The size-rotation counter in a log file's name, 0 for the first file of a period.
+    fn file_counter(&self, path: &Path) -> u32 {
+        let template = self.file_name_template();
+        path.file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| {
+                template
+                    .date_candidates(name)
+                    .into_iter()
+                    .find_map(|date| template.counter(name, date))
+            })
+            .unwrap_or(0)
+    }
+
+    fn parse_period_date(&self, date_str: &str) -> Option<DateTime<Utc>> {
+        let mut parsed = Parsed::new();
+        chrono::format::parse(
+            &mut parsed,
+            date_str,
+            StrftimeItems::new(&self.options.date_pattern),
+        )
+        .ok()?;
+
+        let date = parsed.to_naive_date().ok()?;
+        let time = parsed.to_naive_time().unwrap_or(NaiveTime::MIN);
+        let naive = date.and_time(time);
+
+        self.options.timezone.from_wall_clock(naive)
+    }
+
+    /// The template every log file name follows, built from the configured `filename`.
+    fn file_name_template(&self) -> FileNameTemplate {
+        let base_name = self
+            .options
+            .filename
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("log");
+        FileNameTemplate::new(
+            base_name,
+            self.options.extension.as_deref(),
+            &self.options.filename_variables,
+        )
+    }
+
+    /// The directory scanned for rotated files during cleanup.
+    fn log_dir(&self) -> &Path {
+        self.options
+            .dirname
+            .as_deref()
+            .or_else(|| self.options.filename.parent())
+            .unwrap_or_else(|| Path::new("."))
+    }
+
+    /// Hands `error` to the configured error handler, if any.
+    pub(crate) fn report_error(&self, error: DailyRotateFileError) {
+        if let Some(on_error) = &self.options.on_error {
+            on_error(&error);
+        }
+    }
+}
+
+pub(crate) enum Job {
+    /// Archive the file that was rotated out and apply retention.
+    AfterRotation(PathBuf),
+    /// Compress a file whose previous compression was interrupted.
+    Compress(PathBuf),
+}
+
+/// Runs housekeeping jobs on a dedicated thread, in the order they were submitted.
+pub(crate) struct HousekeepingWorker {
+    sender: Option<Sender<Job>>,
+    handle: Option<JoinHandle<()>>,
+    pending: Arc<(Mutex<usize>, Condvar)>,
+}
+
+impl HousekeepingWorker {
+    pub(crate) fn spawn(housekeeping: Arc<Housekeeping>) -> std::io::Result<Self> {
+        let (sender, receiver) = channel::<Job>();
+        let pending = Arc::new((Mutex::new(0usize), Condvar::new()));
+        let worker_pending = Arc::clone(&pending);
+
+        let handle = std::thread::Builder::new()
+            .name("daily-rotate-file-housekeeping".into())
+            .spawn(move || {
+                for job in receiver {
+                    // A panicking hook must not take the worker down with queued jobs still pending
+                    let _ = std::panic::catch_unwind(AssertUnwindSafe(|| match job {
+                        Job::AfterRotation(previous_file_path) => {
+                            housekeeping.after_rotation(&previous_file_path)
+                        }
+                        Job::Compress(file_path) => {
+                            housekeeping.archive(&file_path);
+                        }
+                    }));
+
+                    let (count, idle) = &*worker_pending;
+                    if let Ok(mut count) = count.lock() {
+                        *count -= 1;
+                        if *count == 0 {
+                            idle.notify_all();
+                        }
+                    }
+                }
+            })?;
+
+        Ok(Self {
+            sender: Some(sender),
+            handle: Some(handle),
+            pending,
+        })
+    }
+
+    pub(crate) fn submit(&self, job: Job) {
+        let (count, _) = &*self.pending;
+        if let Ok(mut count) = count.lock() {
+            *count += 1;
+        }
+
+        let sent = self
+            .sender
+            .as_ref()
+            .is_some_and(|sender| sender.send(job).is_ok());
+
+        if !sent {
+            if let Ok(mut count) = count.lock() {
+                *count -= 1;
+            }
+        }
+    }
+
+    /// Blocks until every submitted job has finished.
+    pub(crate) fn wait_idle(&self) {
+        let (count, idle) = &*self.pending;
+        if let Ok(count) = count.lock() {
+            drop(idle.wait_while(count, |count| *count > 0));
+        }
+    }
+}
+
+impl Drop for HousekeepingWorker {
+    fn drop(&mut self) {
+        // Closing the channel lets the worker drain the queue and exit
+        self.sender.take();
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
@@ -1,3 +1,26 @@
+mod audit;
+mod clock;
+mod compression;
 mod daily_rotate_file;
+mod date_pattern;
+mod error;
+mod filename;
+mod frequency;
+mod housekeeping;
+mod retention;
+mod schedule;
+mod size;
+mod timezone;
 
-pub use daily_rotate_file::DailyRotateFile;
+pub use audit::{AuditEntry, AuditKeep, AuditLog};
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use compression::Compression;
+pub use daily_rotate_file::{
+    default_levels, DailyRotateFile, ErrorHandler, FileHook, RotateHook, WarningHandler,
+};
+pub use error::DailyRotateFileError;
+pub use frequency::Frequency;
+pub use retention::Retention;
+pub use schedule::Schedule;
+pub use size::{deserialize_size, parse_size};
+pub use timezone::Timezone;
@@ -0,0 +1,71 @@
+use crate::error::DailyRotateFileError;
+use std::fmt;
+use std::str::FromStr;
+
+/// How many rotated files to keep, as accepted by `maxFiles` in winston-daily-rotate-file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retention {
+    /// Keep at most this many files, newest first.
+    Count(u32),
+    /// Delete files whose period started more than this many days ago.
+    Days(u32),
+}
+
+impl From<u32> for Retention {
+    fn from(count: u32) -> Self {
+        Retention::Count(count)
+    }
+}
+
+impl fmt::Display for Retention {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Retention::Count(count) => write!(f, "{}", count),
+            Retention::Days(days) => write!(f, "{}d", days),
+        }
+    }
+}
+
+impl FromStr for Retention {
+    type Err = DailyRotateFileError;
+
+    /// Parses `"14"` as a file count and `"14d"` as a number of days.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || {
+            DailyRotateFileError::InvalidConfig(format!(
+                "Invalid max_files '{}': expected a count like \"10\" or days like \"14d\"",
+                s
+            ))
+        };
+
+        match s.strip_suffix(['d', 'D']) {
+            Some(days) => days
+                .trim()
+                .parse()
+                .map(Retention::Days)
+                .map_err(|_| invalid()),
+            None => s.parse().map(Retention::Count).map_err(|_| invalid()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_retention() {
+        assert_eq!("10".parse::<Retention>().unwrap(), Retention::Count(10));
+        assert_eq!("14d".parse::<Retention>().unwrap(), Retention::Days(14));
+        assert_eq!(" 30D ".parse::<Retention>().unwrap(), Retention::Days(30));
+        assert!("".parse::<Retention>().is_err());
+        assert!("d".parse::<Retention>().is_err());
+        assert!("-1".parse::<Retention>().is_err());
+        assert!("2w".parse::<Retention>().is_err());
+        assert_eq!(
+            Retention::Days(7).to_string().parse::<Retention>().unwrap(),
+            Retention::Days(7)
+        );
+    }
+}
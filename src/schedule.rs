@@ -0,0 +1,246 @@
+use crate::error::DailyRotateFileError;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+use std::fmt;
+use std::str::FromStr;
+
+/// How far ahead `next_after` looks before deciding a schedule never fires, e.g. `0 0 30 2 *`.
+/// Long enough to reach the next February 29th.
+const MAX_SEARCH_DAYS: i64 = 8 * 366;
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const DAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A cron expression deciding when the log file is rotated.
+///
+/// The five standard fields are supported: minute, hour, day of month, month and day of week,
+/// each with `*`, lists, ranges, `/` steps and, for months and weekdays, three-letter names.
+/// As in cron, a day matches if either restricted day field matches. `@hourly`, `@daily`,
+/// `@midnight`, `@weekly`, `@monthly` and `@yearly` are accepted as shorthands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    days_of_month_restricted: bool,
+    days_of_week_restricted: bool,
+}
+
+impl Schedule {
+    /// The first minute strictly after `time` at which the schedule fires, as wall-clock time in
+    /// the configured timezone, or `None` if it never does.
+    pub fn next_after(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut next =
+            time.date().and_hms_opt(time.hour(), time.minute(), 0)? + Duration::minutes(1);
+        let limit = time + Duration::days(MAX_SEARCH_DAYS);
+
+        while next <= limit {
+            if !has_bit(self.months, next.month()) {
+                next = first_of_next_month(next.date())?.and_time(NaiveTime::MIN);
+            } else if !self.matches_day(next.date()) {
+                next = next.date().succ_opt()?.and_time(NaiveTime::MIN);
+            } else if !has_bit(self.hours, next.hour()) {
+                next = next.date().and_hms_opt(next.hour(), 0, 0)? + Duration::hours(1);
+            } else if !has_bit(self.minutes, next.minute()) {
+                next += Duration::minutes(1);
+            } else {
+                return Some(next);
+            }
+        }
+
+        None
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day_of_month = has_bit(self.days_of_month, date.day());
+        let day_of_week = has_bit(self.days_of_week, date.weekday().num_days_from_sunday());
+
+        match (self.days_of_month_restricted, self.days_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            (true, false) => day_of_month,
+            (false, true) => day_of_week,
+            (false, false) => true,
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.expression)
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = DailyRotateFileError;
+
+    /// Parses a five-field cron expression such as `"0 2 * * *"` or `"30 6 * * mon"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = |reason: &str| {
+            DailyRotateFileError::InvalidConfig(format!("Invalid schedule '{}': {}", s, reason))
+        };
+
+        let expanded = match s.to_ascii_lowercase().as_str() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            _ => s,
+        };
+
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(invalid("expected 5 fields"));
+        };
+
+        let parse = |field: &str, min: u32, max: u32, names: &[&str]| {
+            parse_field(field, min, max, names).map_err(|reason| invalid(&reason))
+        };
+
+        // Sunday may be written as 7
+        let mut days_of_week = parse(day_of_week, 0, 7, &DAY_NAMES)?;
+        if has_bit(days_of_week, 7) {
+            days_of_week |= 1;
+        }
+
+        Ok(Schedule {
+            expression: s.to_string(),
+            minutes: parse(minute, 0, 59, &[])?,
+            hours: parse(hour, 0, 23, &[])?,
+            days_of_month: parse(day_of_month, 1, 31, &[])?,
+            months: parse(month, 1, 12, &MONTH_NAMES)?,
+            days_of_week,
+            days_of_month_restricted: day_of_month != "*",
+            days_of_week_restricted: day_of_week != "*",
+        })
+    }
+}
+
+/// Parses one cron field into a bit set of the allowed values. `names[i]` stands for `min + i`.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let value = |text: &str| -> Result<u32, String> {
+        let lower = text.to_ascii_lowercase();
+        let value = match names.iter().position(|name| *name == lower) {
+            Some(index) => min + index as u32,
+            None => text
+                .parse()
+                .map_err(|_| format!("'{}' is not a number", text))?,
+        };
+        if (min..=max).contains(&value) {
+            Ok(value)
+        } else {
+            Err(format!("{} is outside {}-{}", value, min, max))
+        }
+    };
+
+    let mut bits = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => return Err(format!("invalid step in '{}'", item)),
+            },
+            None => (item, 1),
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // "5/15" runs from 5 to the end of the range
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let single = value(range)?;
+                    (single, single)
+                }
+            },
+        };
+
+        if start > end {
+            return Err(format!("empty range '{}'", range));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            bits |= 1 << value;
+        }
+    }
+
+    Ok(bits)
+}
+
+fn has_bit(bits: u64, value: u32) -> bool {
+    bits & (1 << value) != 0
+}
+
+fn first_of_next_month(date: NaiveDate) -> Option<NaiveDate> {
+    match date.month() {
+        12 => NaiveDate::from_ymd_opt(date.year() + 1, 1, 1),
+        month => NaiveDate::from_ymd_opt(date.year(), month + 1, 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn next(expression: &str, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        expression.parse::<Schedule>().unwrap().next_after(time)
+    }
+
+    #[test]
+    fn test_next_after() {
+        // 2026-10-16 is a Friday
+        assert_eq!(next("0 2 * * *", at(16, 1, 59)), Some(at(16, 2, 0)));
+        assert_eq!(next("0 2 * * *", at(16, 2, 0)), Some(at(17, 2, 0)));
+        assert_eq!(next("*/15 * * * *", at(16, 10, 44)), Some(at(16, 10, 45)));
+        assert_eq!(next("0 0 * * mon", at(16, 12, 0)), Some(at(19, 0, 0)));
+        assert_eq!(next("@hourly", at(16, 23, 30)), Some(at(17, 0, 0)));
+        assert_eq!(
+            next("0 0 1 jan *", at(16, 0, 0)),
+            NaiveDate::from_ymd_opt(2027, 1, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+        );
+        assert_eq!(next("0 0 30 2 *", at(16, 0, 0)), None);
+    }
+
+    #[test]
+    fn test_day_fields_match_either() {
+        // The 20th, or any Sunday
+        let schedule: Schedule = "0 0 20 * 7".parse().unwrap();
+        assert_eq!(schedule.next_after(at(16, 0, 0)), Some(at(18, 0, 0)));
+        assert_eq!(schedule.next_after(at(18, 0, 0)), Some(at(20, 0, 0)));
+    }
+
+    #[test]
+    fn test_invalid_schedules() {
+        for invalid in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "* * * * funday",
+        ] {
+            assert!(
+                invalid.parse::<Schedule>().is_err(),
+                "Expected '{}' to fail",
+                invalid
+            );
+        }
+    }
+}
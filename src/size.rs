@@ -0,0 +1,92 @@
+use crate::error::DailyRotateFileError;
+use serde::{Deserialize, Deserializer};
+
+/// Parses a size such as `"500k"`, `"10m"`, `"1g"` or `"20MiB"` into bytes.
+///
+/// Suffixes are case-insensitive and, as in winston-daily-rotate-file, binary: `k`, `kb` and
+/// `kib` all mean 1024 bytes. A bare number (or a `b` suffix) is a byte count.
+pub fn parse_size(size: &str) -> Result<u64, DailyRotateFileError> {
+    let invalid = || {
+        DailyRotateFileError::InvalidConfig(format!(
+            "Invalid size '{}': expected bytes or a k/m/g suffix like \"10m\"",
+            size
+        ))
+    };
+
+    let trimmed = size.trim();
+    let split_at = trimmed
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split_at);
+
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => 1024 * 1024,
+        "g" | "gb" | "gib" => 1024 * 1024 * 1024,
+        _ => return Err(invalid()),
+    };
+
+    number.checked_mul(multiplier).ok_or_else(invalid)
+}
+
+/// Deserializes a size given either as a byte count or as a string accepted by [`parse_size`],
+/// for use with `#[serde(deserialize_with = "deserialize_size")]`.
+pub fn deserialize_size<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum RawSize {
+        Bytes(u64),
+        Text(String),
+    }
+
+    match RawSize::deserialize(deserializer)? {
+        RawSize::Bytes(bytes) => Ok(bytes),
+        RawSize::Text(text) => parse_size(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("1024").unwrap(), 1024);
+        assert_eq!(parse_size("12b").unwrap(), 12);
+        assert_eq!(parse_size("500k").unwrap(), 500 * 1024);
+        assert_eq!(parse_size("10m").unwrap(), 10 * 1024 * 1024);
+        assert_eq!(parse_size("1g").unwrap(), 1024 * 1024 * 1024);
+        assert_eq!(parse_size("20MiB").unwrap(), 20 * 1024 * 1024);
+        assert_eq!(parse_size(" 3 KB ").unwrap(), 3 * 1024);
+
+        for invalid in ["", "m", "10x", "1.5m", "-1k", "99999999999g"] {
+            assert!(
+                parse_size(invalid).is_err(),
+                "Expected '{}' to fail",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_deserialize_size() {
+        #[derive(Deserialize)]
+        struct Config {
+            #[serde(deserialize_with = "deserialize_size")]
+            max_size: u64,
+        }
+
+        let config: Config = serde_json::from_str(r#"{"max_size": "20m"}"#).unwrap();
+        assert_eq!(config.max_size, 20 * 1024 * 1024);
+
+        let config: Config = serde_json::from_str(r#"{"max_size": 4096}"#).unwrap();
+        assert_eq!(config.max_size, 4096);
+
+        assert!(serde_json::from_str::<Config>(r#"{"max_size": "lots"}"#).is_err());
+    }
+}
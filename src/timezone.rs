@@ -0,0 +1,231 @@
+use crate::error::DailyRotateFileError;
+use chrono::{DateTime, Duration, FixedOffset, Local, NaiveDateTime, TimeZone, Utc};
+use std::fmt;
+use std::str::FromStr;
+
+/// The longest run of wall-clock time skipped by a timezone transition, with room to spare.
+const MAX_GAP_MINUTES: i64 = 24 * 60;
+
+/// The timezone in which periods start and dates in file names are written.
+///
+/// Non-exhaustive because the `chrono-tz` feature, enabled anywhere in the dependency graph,
+/// adds the `Named` variant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[non_exhaustive]
+pub enum Timezone {
+    /// The host's local timezone.
+    #[default]
+    Local,
+    Utc,
+    /// A fixed offset from UTC, such as `+05:30`.
+    Fixed(FixedOffset),
+    /// An IANA timezone such as `America/New_York`, following its DST rules.
+    #[cfg(feature = "chrono-tz")]
+    Named(chrono_tz::Tz),
+}
+
+impl Timezone {
+    /// `date` as wall-clock time in this timezone.
+    pub fn wall_clock(&self, date: &DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Timezone::Local => date.with_timezone(&Local).naive_local(),
+            Timezone::Utc => date.naive_utc(),
+            Timezone::Fixed(offset) => date.with_timezone(offset).naive_local(),
+            #[cfg(feature = "chrono-tz")]
+            Timezone::Named(tz) => date.with_timezone(tz).naive_local(),
+        }
+    }
+
+    /// `date` formatted with the strftime `pattern` in this timezone.
+    pub fn format(&self, date: &DateTime<Utc>, pattern: &str) -> String {
+        match self {
+            Timezone::Local => date.with_timezone(&Local).format(pattern).to_string(),
+            Timezone::Utc => date.format(pattern).to_string(),
+            Timezone::Fixed(offset) => date.with_timezone(offset).format(pattern).to_string(),
+            #[cfg(feature = "chrono-tz")]
+            Timezone::Named(tz) => date.with_timezone(tz).format(pattern).to_string(),
+        }
+    }
+
+    /// The instant a wall-clock `time` in this timezone stands for. A time repeated when the
+    /// clocks go back maps to its first occurrence; a time skipped when they go forward maps to
+    /// the moment they did.
+    pub fn from_wall_clock(&self, time: NaiveDateTime) -> Option<DateTime<Utc>> {
+        match self {
+            Timezone::Local => resolve(&Local, time),
+            Timezone::Utc => Some(time.and_utc()),
+            Timezone::Fixed(offset) => resolve(offset, time),
+            #[cfg(feature = "chrono-tz")]
+            Timezone::Named(tz) => resolve(tz, time),
+        }
+    }
+}
+
+/// `time` in `tz`, moved forward to the end of the gap if it falls into one.
+fn resolve<Tz: TimeZone>(tz: &Tz, time: NaiveDateTime) -> Option<DateTime<Utc>> {
+    (0..=MAX_GAP_MINUTES).find_map(|minutes| {
+        (time + Duration::minutes(minutes))
+            .and_local_timezone(tz.clone())
+            .earliest()
+            .map(|date| date.with_timezone(&Utc))
+    })
+}
+
+impl fmt::Display for Timezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Timezone::Local => write!(f, "local"),
+            Timezone::Utc => write!(f, "UTC"),
+            Timezone::Fixed(offset) => write!(f, "{}", offset),
+            #[cfg(feature = "chrono-tz")]
+            Timezone::Named(tz) => write!(f, "{}", tz.name()),
+        }
+    }
+}
+
+impl FromStr for Timezone {
+    type Err = DailyRotateFileError;
+
+    /// Parses `"local"`, `"UTC"`, offsets like `"+05:30"`, `"-0800"` or `"UTC+2"` and, with the
+    /// `chrono-tz` feature, IANA names like `"America/New_York"`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || {
+            let hint = if cfg!(feature = "chrono-tz") {
+                ""
+            } else {
+                " (IANA names need the chrono-tz feature)"
+            };
+            DailyRotateFileError::InvalidConfig(format!(
+                "Invalid timezone '{}': expected \"local\", \"UTC\" or an offset like \"+05:30\"{}",
+                s, hint
+            ))
+        };
+
+        match s.to_ascii_lowercase().as_str() {
+            "local" => return Ok(Timezone::Local),
+            "utc" | "gmt" | "z" => return Ok(Timezone::Utc),
+            _ => {}
+        }
+
+        let offset = s
+            .strip_prefix("UTC")
+            .or_else(|| s.strip_prefix("GMT"))
+            .unwrap_or(s);
+        if offset.starts_with(['+', '-']) {
+            return parse_offset(offset)
+                .map(Timezone::Fixed)
+                .ok_or_else(invalid);
+        }
+
+        #[cfg(feature = "chrono-tz")]
+        if let Ok(tz) = s.parse::<chrono_tz::Tz>() {
+            return Ok(Timezone::Named(tz));
+        }
+
+        Err(invalid())
+    }
+}
+
+/// Parses `+HH`, `+HHMM` or `+HH:MM` (and the `-` equivalents).
+fn parse_offset(offset: &str) -> Option<FixedOffset> {
+    let (sign, rest) = offset.split_at(1);
+    let digits: String = rest.chars().filter(|c| *c != ':').collect();
+    if digits.is_empty() || digits.len() > 4 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let (hours, minutes) = if digits.len() <= 2 {
+        (digits.parse::<i32>().ok()?, 0)
+    } else {
+        let (hours, minutes) = digits.split_at(digits.len() - 2);
+        (hours.parse::<i32>().ok()?, minutes.parse::<i32>().ok()?)
+    };
+    if hours > 23 || minutes > 59 {
+        return None;
+    }
+
+    let seconds = (hours * 60 + minutes) * 60;
+    FixedOffset::east_opt(if sign == "-" { -seconds } else { seconds })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timezone() {
+        assert_eq!("local".parse::<Timezone>().unwrap(), Timezone::Local);
+        assert_eq!("utc".parse::<Timezone>().unwrap(), Timezone::Utc);
+        assert_eq!(
+            "+05:30".parse::<Timezone>().unwrap(),
+            Timezone::Fixed(FixedOffset::east_opt(5 * 3600 + 30 * 60).unwrap())
+        );
+        assert_eq!(
+            "UTC-8".parse::<Timezone>().unwrap(),
+            Timezone::Fixed(FixedOffset::west_opt(8 * 3600).unwrap())
+        );
+        assert_eq!(
+            "-0800".parse::<Timezone>().unwrap(),
+            "UTC-08:00".parse::<Timezone>().unwrap()
+        );
+
+        for invalid in ["", "+", "+24:00", "+05:60", "+123456", "Mars/Olympus_Mons"] {
+            assert!(
+                invalid.parse::<Timezone>().is_err(),
+                "Expected '{}' to fail",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn test_fixed_offset_wall_clock() {
+        let timezone: Timezone = "-05:00".parse().unwrap();
+        let date = DateTime::parse_from_rfc3339("2026-10-17T03:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        assert_eq!(timezone.format(&date, "%Y-%m-%d %H"), "2026-10-16 22");
+        assert_eq!(
+            timezone.from_wall_clock(timezone.wall_clock(&date)),
+            Some(date)
+        );
+    }
+
+    #[cfg(not(feature = "chrono-tz"))]
+    #[test]
+    fn test_iana_name_needs_feature() {
+        let error = "America/New_York".parse::<Timezone>().unwrap_err();
+        assert!(error.to_string().contains("chrono-tz feature"));
+    }
+
+    #[cfg(feature = "chrono-tz")]
+    #[test]
+    fn test_named_timezone() {
+        let timezone: Timezone = "America/New_York".parse().unwrap();
+        assert_eq!(timezone.to_string(), "America/New_York");
+
+        // 03:30 UTC is still the previous day in New York (EDT, UTC-4)
+        let date = DateTime::parse_from_rfc3339("2026-10-17T03:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        assert_eq!(timezone.format(&date, "%Y-%m-%d"), "2026-10-16");
+    }
+
+    #[cfg(feature = "chrono-tz")]
+    #[test]
+    fn test_skipped_time_maps_to_the_transition() {
+        let timezone: Timezone = "America/New_York".parse().unwrap();
+        // 02:30 does not exist on 2026-03-08: the clocks jump from 02:00 EST to 03:00 EDT
+        let skipped = chrono::NaiveDate::from_ymd_opt(2026, 3, 8)
+            .unwrap()
+            .and_hms_opt(2, 30, 0)
+            .unwrap();
+
+        assert_eq!(
+            timezone.from_wall_clock(skipped).unwrap().to_rfc3339(),
+            "2026-03-08T07:00:00+00:00"
+        );
+    }
+}